@@ -1,5 +1,16 @@
+// Only for the IDL handlers #[program] generates, which still call the deprecated AccountInfo::realloc. They
+// are emitted in a private module next to `snake_game`, so an allow on the program module doesn't reach them.
+#![allow(deprecated)]
+
 use anchor_lang::{prelude::*, solana_program};
 use anchor_lang::solana_program::clock::Clock;
+use anchor_spl::token_interface::{
+    self, CloseAccount, HarvestWithheldTokensToMint, Mint, Token2022, TokenAccount, TokenInterface, TransferChecked,
+};
+use anchor_spl::token_interface::spl_token_2022::extension::{
+    transfer_fee::TransferFeeAmount, BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use anchor_spl::token_interface::spl_token_2022::state::{Account as SplAccount, Mint as SplMint};
 use solana_program::sysvar::instructions::{load_instruction_at_checked, load_current_index_checked};
 
 declare_id!("47aZBskQcoKBXr4nLn2gy7CjSWDo33PytLaeMET2FfBv");
@@ -8,19 +19,576 @@ declare_id!("47aZBskQcoKBXr4nLn2gy7CjSWDo33PytLaeMET2FfBv");
 pub mod snake_game {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        oracles: Vec<Pubkey>,
+        oracle_threshold: u8,
+        commission_claimer: Pubkey,
+    ) -> Result<()> {
         let contract_state = &mut ctx.accounts.contract_state;
         
         // Initialize with zero commission (Anchor init ensures this is a fresh account)
+        contract_state.version = CONTRACT_STATE_VERSION;
         contract_state.accumulated_commission = 0;
         
+        // Upgrade authority becomes the admin; the backend oracles are a separate role
+        init_global_config(
+            &mut ctx.accounts.global_config,
+            ctx.accounts.authority.key(),
+            oracles,
+            oracle_threshold,
+            commission_claimer,
+            ctx.bumps.global_config,
+        )?;
+        
         // Commission vault is now created automatically by Anchor with init attribute
         
         Ok(())
     }
 
-    pub fn create_lobby(ctx: Context<CreateLobby>, bet_amount: u64, lobby_id: String, referrer: Option<Pubkey>) -> Result<()> {
-        require!(bet_amount >= MIN_BET_AMOUNT, GameError::BetAmountTooSmall);
+    // Second step of an admin rotation. The first step is an executed TransferAdmin multisig proposal, which
+    // replaces a single-key propose_admin so the admin can't be rotated, or the rotation blocked, by one key.
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let global_config = &mut ctx.accounts.global_config;
+        let old_admin = global_config.admin;
+        let new_admin = ctx.accounts.new_admin.key();
+        
+        global_config.admin = new_admin;
+        global_config.pending_admin = None;
+        
+        emit!(AdminUpdated {
+            old_admin,
+            new_admin,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    // Two timelocked queues exist on purpose. Player-facing terms (fees, timeouts, the delay itself) are
+    // queued by the admin here and the admin can cancel them, because they are bounded by protocol limits
+    // and never touch lobbies, spectator pools or sponsorships that already exist. Authorities and anything
+    // that moves funds go through multisig proposals instead (execute_proposal), so a single key can neither
+    // rotate them nor veto a rotation.
+    pub fn queue_config_change(ctx: Context<QueueConfigChange>, change: ConfigChange) -> Result<()> {
+        validate_config_change(&change)?;
+        
+        let global_config = &mut ctx.accounts.global_config;
+        let queued_change = &mut ctx.accounts.queued_change;
+        let clock = Clock::get()?;
+        
+        // Players get at least `timelock_delay` seconds of notice before the change can apply
+        let eta = clock.unix_timestamp.checked_add(global_config.timelock_delay)
+            .ok_or(GameError::ArithmeticOverflow)?;
+        
+        queued_change.id = global_config.config_change_count;
+        queued_change.change = change;
+        queued_change.queued_by = ctx.accounts.admin.key();
+        queued_change.queued_at = clock.unix_timestamp;
+        queued_change.eta = eta;
+        queued_change.bump = ctx.bumps.queued_change;
+        
+        global_config.config_change_count = global_config.config_change_count.checked_add(1)
+            .ok_or(GameError::ArithmeticOverflow)?;
+        
+        emit!(ConfigChangeQueued {
+            change_id: queued_change.id,
+            change: queued_change.change.clone(),
+            eta,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn execute_config_change(ctx: Context<ExecuteConfigChange>) -> Result<()> {
+        let queued_change = &ctx.accounts.queued_change;
+        let clock = Clock::get()?;
+        
+        require!(clock.unix_timestamp >= queued_change.eta, GameError::TimelockNotExpired);
+        
+        // Re-validate in case bounds it depends on changed while it was queued
+        validate_config_change(&queued_change.change)?;
+        
+        // Only affects lobbies created from now on; existing lobbies keep their snapshot
+        let global_config = &mut ctx.accounts.global_config;
+        match queued_change.change.clone() {
+            ConfigChange::CommissionRates { commission_bps, referrer_share_bps } => {
+                global_config.commission_bps = commission_bps;
+                global_config.referrer_share_bps = referrer_share_bps;
+                
+                emit!(CommissionRatesUpdated {
+                    commission_bps,
+                    referrer_share_bps,
+                    timestamp: clock.unix_timestamp,
+                });
+            },
+            ConfigChange::TimeoutBounds { min_timeout_seconds, max_timeout_seconds } => {
+                global_config.min_timeout_seconds = min_timeout_seconds;
+                global_config.max_timeout_seconds = max_timeout_seconds;
+                
+                emit!(TimeoutBoundsUpdated {
+                    min_timeout_seconds,
+                    max_timeout_seconds,
+                    timestamp: clock.unix_timestamp,
+                });
+            },
+            ConfigChange::TimelockDelay { delay_seconds } => {
+                global_config.timelock_delay = delay_seconds;
+                
+                emit!(TimelockDelayUpdated {
+                    delay_seconds,
+                    timestamp: clock.unix_timestamp,
+                });
+            },
+            ConfigChange::SpectatorTerms { commission_bps, cutoff_seconds } => {
+                global_config.spectator_commission_bps = commission_bps;
+                global_config.spectator_cutoff_seconds = cutoff_seconds;
+                
+                emit!(SpectatorTermsUpdated {
+                    commission_bps,
+                    cutoff_seconds,
+                    timestamp: clock.unix_timestamp,
+                });
+            },
+            ConfigChange::BonusCommission { commission_bps } => {
+                global_config.bonus_commission_bps = commission_bps;
+                
+                emit!(BonusCommissionUpdated {
+                    commission_bps,
+                    timestamp: clock.unix_timestamp,
+                });
+            },
+        }
+        
+        // Queued change account is closed to its payer by the close attribute
+        
+        emit!(ConfigChangeExecuted {
+            change_id: queued_change.id,
+            change: queued_change.change.clone(),
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn cancel_config_change(ctx: Context<CancelConfigChange>) -> Result<()> {
+        // Queued change account is closed to its payer by the close attribute
+        emit!(ConfigChangeCancelled {
+            change_id: ctx.accounts.queued_change.id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn set_pause_flags(ctx: Context<UpdateConfig>, paused_flags: u8) -> Result<()> {
+        validate_pause_flags(paused_flags)?;
+        
+        // Single-key emergency stop so the admin can react before a multisig round-trip. Refund paths
+        // (draw refunds, timeout cancels, closing lobbies) are never pausable, so a pause can't hold funds.
+        ctx.accounts.global_config.paused_flags = paused_flags;
+        
+        emit!(PauseFlagsUpdated {
+            paused_flags,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_bet_limits(ctx: Context<UpdateBetLimits>, max_bet_amount: u64, bet_tiers: Vec<u64>) -> Result<()> {
+        validate_bet_limits(max_bet_amount, &bet_tiers)?;
+        
+        // Lowering the max is a risk control the admin applies alone and immediately; growing stake sizes
+        // needs an approved RaiseMaxBet proposal
+        approve_max_bet_raise(
+            ctx.accounts.multisig.as_deref(),
+            ctx.accounts.proposal.as_deref_mut(),
+            None,
+            ctx.accounts.global_config.max_bet_amount,
+            max_bet_amount,
+            ctx.accounts.admin.key(),
+        )?;
+        
+        let global_config = &mut ctx.accounts.global_config;
+        global_config.max_bet_amount = max_bet_amount;
+        global_config.bet_tiers = bet_tiers;
+        
+        emit!(BetLimitsUpdated {
+            max_bet_amount,
+            bet_tiers: global_config.bet_tiers.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn add_stake_mint(ctx: Context<AddStakeMint>, min_bet_amount: u64, max_bet_amount: u64) -> Result<()> {
+        require!(min_bet_amount > 0 && min_bet_amount <= max_bet_amount, GameError::InvalidBetLimits);
+        // Mint extensions are fixed at mint creation, so checking them once when allowing the mint is enough
+        validate_mint_extensions(&ctx.accounts.mint)?;
+        
+        // A new stake asset starts from no limit at all, so it always needs the multisig's approval
+        approve_max_bet_raise(
+            Some(&ctx.accounts.multisig),
+            Some(&mut ctx.accounts.proposal),
+            Some(ctx.accounts.mint.key()),
+            0,
+            max_bet_amount,
+            ctx.accounts.admin.key(),
+        )?;
+        
+        // Commission token vault is created by Anchor with init attribute alongside the registry entry
+        let stake_mint = &mut ctx.accounts.stake_mint;
+        stake_mint.mint = ctx.accounts.mint.key();
+        stake_mint.decimals = ctx.accounts.mint.decimals;
+        stake_mint.min_bet_amount = min_bet_amount;
+        stake_mint.max_bet_amount = max_bet_amount;
+        stake_mint.enabled = true;
+        stake_mint.bump = ctx.bumps.stake_mint;
+        
+        // Each allowed mint gets its own commission ledger
+        let contract_state = &mut ctx.accounts.contract_state;
+        require!(contract_state.token_commissions.len() < MAX_STAKE_MINTS, GameError::TooManyStakeMints);
+        contract_state.token_commissions.push(CommissionLedger {
+            mint: stake_mint.mint,
+            accumulated_commission: 0,
+        });
+        
+        emit!(StakeMintUpdated {
+            mint: stake_mint.mint,
+            decimals: stake_mint.decimals,
+            min_bet_amount,
+            max_bet_amount,
+            enabled: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn update_stake_mint(
+        ctx: Context<UpdateStakeMint>,
+        min_bet_amount: u64,
+        max_bet_amount: u64,
+        enabled: bool,
+    ) -> Result<()> {
+        require!(min_bet_amount > 0 && min_bet_amount <= max_bet_amount, GameError::InvalidBetLimits);
+        
+        // Same rule as the SOL bet limits: lowering applies immediately, raising needs a RaiseMaxBet proposal.
+        // Disabling only blocks new lobbies, and re-enabling restores limits the multisig already approved.
+        approve_max_bet_raise(
+            ctx.accounts.multisig.as_deref(),
+            ctx.accounts.proposal.as_deref_mut(),
+            Some(ctx.accounts.stake_mint.mint),
+            ctx.accounts.stake_mint.max_bet_amount,
+            max_bet_amount,
+            ctx.accounts.admin.key(),
+        )?;
+        
+        let stake_mint = &mut ctx.accounts.stake_mint;
+        stake_mint.min_bet_amount = min_bet_amount;
+        stake_mint.max_bet_amount = max_bet_amount;
+        stake_mint.enabled = enabled;
+        
+        emit!(StakeMintUpdated {
+            mint: stake_mint.mint,
+            decimals: stake_mint.decimals,
+            min_bet_amount,
+            max_bet_amount,
+            enabled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn create_multisig(ctx: Context<CreateMultisig>, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        // One-time bootstrap: the multisig PDA can only be initialized once, later changes need UpdateMultisig
+        validate_signer_set(&signers, threshold, MAX_MULTISIG_SIGNERS, GameError::InvalidMultisigConfig)?;
+        
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.signers = signers;
+        multisig.threshold = threshold;
+        multisig.proposal_count = 0;
+        multisig.bump = ctx.bumps.multisig;
+        
+        emit!(MultisigUpdated {
+            signers: multisig.signers.clone(),
+            threshold,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: ProposalAction) -> Result<()> {
+        match &action {
+            ProposalAction::UpdateMultisig { signers, threshold } => {
+                validate_signer_set(signers, *threshold, MAX_MULTISIG_SIGNERS, GameError::InvalidMultisigConfig)?;
+            },
+            ProposalAction::SetOracles { oracles, threshold } => {
+                validate_signer_set(oracles, *threshold, MAX_ORACLES, GameError::InvalidOracleSet)?;
+            },
+            ProposalAction::SetTreasuryRecipients { recipients } => {
+                validate_treasury_recipients(recipients)?;
+            },
+            ProposalAction::TransferAdmin { new_admin } => {
+                require!(*new_admin != Pubkey::default(), GameError::InvalidAuthority);
+            },
+            ProposalAction::RaiseMaxBet { max_bet_amount, .. } => {
+                require!(*max_bet_amount >= MIN_BET_AMOUNT, GameError::InvalidBetLimits);
+            },
+            ProposalAction::CancelProposal { proposal_id } => {
+                // Can only target an earlier proposal, never itself
+                require!(*proposal_id < ctx.accounts.multisig.proposal_count, GameError::InvalidProposalAction);
+            },
+            _ => {},
+        }
+        
+        let multisig = &mut ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
+        let proposer = ctx.accounts.proposer.key();
+        
+        proposal.id = multisig.proposal_count;
+        proposal.proposer = proposer;
+        proposal.action = action;
+        // Proposer implicitly approves their own proposal
+        proposal.approvals = vec![proposer];
+        proposal.executed = false;
+        proposal.created_at = clock.unix_timestamp;
+        proposal.eta = None;
+        proposal.bump = ctx.bumps.proposal;
+        
+        multisig.proposal_count = multisig.proposal_count.checked_add(1).ok_or(GameError::ArithmeticOverflow)?;
+        
+        emit!(ProposalCreated {
+            proposal_id: proposal.id,
+            proposer,
+            action: proposal.action.clone(),
+            timestamp: clock.unix_timestamp,
+        });
+        
+        // A threshold of one is already met by the proposer's own approval
+        start_proposal_timelock(proposal, multisig, ctx.accounts.global_config.timelock_delay, clock.unix_timestamp)?;
+        
+        Ok(())
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let approver = ctx.accounts.approver.key();
+        let clock = Clock::get()?;
+        
+        require!(!proposal.executed, GameError::ProposalAlreadyExecuted);
+        require!(!proposal.approvals.contains(&approver), GameError::ProposalAlreadyApproved);
+        
+        // Drop approvals from signers removed since the proposal was created to keep within max_len
+        let multisig = &ctx.accounts.multisig;
+        proposal.approvals.retain(|signer| multisig.is_signer(signer));
+        proposal.approvals.push(approver);
+        
+        emit!(ProposalApproved {
+            proposal_id: proposal.id,
+            approver,
+            approvals: proposal.approvals.len() as u64,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        start_proposal_timelock(proposal, multisig, ctx.accounts.global_config.timelock_delay, clock.unix_timestamp)?;
+        
+        Ok(())
+    }
+
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
+        
+        require!(!proposal.executed, GameError::ProposalAlreadyExecuted);
+        require!(
+            multisig.approval_count(&proposal.approvals) >= multisig.threshold as usize,
+            GameError::ProposalThresholdNotMet
+        );
+        
+        // Approvals can meet a threshold lowered by UpdateMultisig after the last approve_proposal, leaving
+        // no eta. Start the notice period now instead of executing, the proposal runs once it has passed.
+        let Some(eta) = proposal.eta else {
+            require!(proposal.action.is_timelocked(), GameError::InvalidProposalAction);
+            start_proposal_timelock(proposal, multisig, ctx.accounts.global_config.timelock_delay, clock.unix_timestamp)?;
+            return Ok(());
+        };
+        
+        // Authority changes only apply once the notice period has passed
+        require!(clock.unix_timestamp >= eta, GameError::TimelockNotExpired);
+        
+        match proposal.action.clone() {
+            ProposalAction::SetOracles { oracles, threshold } => {
+                let global_config = &mut ctx.accounts.global_config;
+                global_config.oracles = oracles;
+                global_config.oracle_threshold = threshold;
+                
+                emit!(OracleSetUpdated {
+                    oracles: global_config.oracles.clone(),
+                    threshold,
+                    timestamp: clock.unix_timestamp,
+                });
+            },
+            ProposalAction::SetCommissionClaimer { new_commission_claimer } => {
+                require!(new_commission_claimer != Pubkey::default(), GameError::InvalidAuthority);
+                
+                let global_config = &mut ctx.accounts.global_config;
+                let old_commission_claimer = global_config.commission_claimer;
+                global_config.commission_claimer = new_commission_claimer;
+                
+                emit!(CommissionClaimerUpdated {
+                    old_commission_claimer,
+                    new_commission_claimer,
+                    timestamp: clock.unix_timestamp,
+                });
+            },
+            ProposalAction::UpdateMultisig { signers, threshold } => {
+                // Approvals on other pending proposals only count for signers still in the set
+                multisig.signers = signers;
+                multisig.threshold = threshold;
+                
+                emit!(MultisigUpdated {
+                    signers: multisig.signers.clone(),
+                    threshold,
+                    timestamp: clock.unix_timestamp,
+                });
+            },
+            ProposalAction::SetTreasuryRecipients { recipients } => {
+                let global_config = &mut ctx.accounts.global_config;
+                global_config.treasury_recipients = recipients;
+                
+                emit!(TreasuryRecipientsUpdated {
+                    recipients: global_config.treasury_recipients.clone(),
+                    timestamp: clock.unix_timestamp,
+                });
+            },
+            ProposalAction::TransferAdmin { new_admin } => {
+                // Admin only changes once the new key accepts; a later transfer replaces the pending admin
+                let global_config = &mut ctx.accounts.global_config;
+                global_config.pending_admin = Some(new_admin);
+                
+                emit!(AdminTransferProposed {
+                    current_admin: global_config.admin,
+                    pending_admin: new_admin,
+                    timestamp: clock.unix_timestamp,
+                });
+            },
+            ProposalAction::WithdrawCommission { .. }
+            | ProposalAction::WithdrawTokenCommission { .. }
+            | ProposalAction::RaiseMaxBet { .. }
+            | ProposalAction::CancelProposal { .. } => {
+                // Withdrawals are executed through claim_commission / claim_token_commission, limit raises
+                // through update_bet_limits / add_stake_mint / update_stake_mint, cancellations through
+                // cancel_proposal
+                return Err(GameError::InvalidProposalAction.into());
+            },
+        }
+        
+        proposal.executed = true;
+        
+        emit!(ProposalExecuted {
+            proposal_id: proposal.id,
+            executor: ctx.accounts.executor.key(),
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        let clock = Clock::get()?;
+        let proposal_id = ctx.accounts.proposal.id;
+        
+        // Needs an approved CancelProposal for this exact proposal, so no single key can veto
+        let cancellation = &mut ctx.accounts.cancellation;
+        require!(
+            cancellation.action == ProposalAction::CancelProposal { proposal_id },
+            GameError::InvalidProposalAction
+        );
+        execute_approved_proposal(cancellation, &ctx.accounts.multisig)?;
+        
+        ctx.accounts.proposal.check_cancellable(clock.unix_timestamp)?;
+        
+        // Cancelled proposal account is closed to its proposer by the close attribute
+        
+        emit!(ProposalCancelled {
+            proposal_id,
+            cancellation_id: cancellation.id,
+            executor: ctx.accounts.executor.key(),
+            timestamp: clock.unix_timestamp,
+        });
+        
+        emit!(ProposalExecuted {
+            proposal_id: cancellation.id,
+            executor: ctx.accounts.executor.key(),
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_lobby(
+        ctx: Context<CreateLobby>,
+        bet_amount: u64,
+        lobby_id: String,
+        referrer: Option<Pubkey>,
+        join_timeout_seconds: i64,
+        play_timeout_seconds: i64,
+        max_players: u8,
+        payout_bps: Vec<u16>,
+        team_mode: bool,
+        invite: Option<LobbyInvite>,
+        series_length: u8,
+    ) -> Result<()> {
+        let global_config = &ctx.accounts.global_config;
+        require!(!global_config.is_paused(PAUSE_CREATE_LOBBY), GameError::InstructionPaused);
+        require!((MIN_PLAYERS..=MAX_PLAYERS).contains(&max_players), GameError::InvalidMaxPlayers);
+        // Empty payout table means winner takes all
+        if !payout_bps.is_empty() {
+            validate_payout_table(&payout_bps, max_players)?;
+        }
+        // Team lobbies are always two full teams sharing the pot of the winning team
+        if team_mode {
+            require!(max_players == TEAM_COUNT * TEAM_SIZE, GameError::InvalidMaxPlayers);
+            require!(payout_bps.is_empty(), GameError::InvalidPayoutTable);
+        }
+        // Best-of-N series are plain 1v1s with an odd number of rounds, 1 being a single game
+        require!(series_length % 2 == 1 && series_length <= MAX_SERIES_LENGTH, GameError::InvalidSeriesLength);
+        if series_length > 1 {
+            require!(
+                max_players == MIN_PLAYERS && !team_mode && payout_bps.is_empty(),
+                GameError::InvalidSeriesLength
+            );
+        }
+        match invite {
+            // A designated opponent only makes sense for a plain 1v1
+            Some(LobbyInvite::Opponent(opponent)) => {
+                require!(max_players == MIN_PLAYERS && !team_mode, GameError::InvalidInvite);
+                require!(opponent != ctx.accounts.creator.key(), GameError::InvalidInvite);
+            },
+            Some(LobbyInvite::InviteKey(invite_key)) => {
+                require!(invite_key != Pubkey::default(), GameError::InvalidInvite);
+            },
+            None => {},
+        }
+        
+        // Passing a mint makes this an SPL lobby, otherwise stakes are in SOL
+        let token = vault_token(
+            ctx.accounts.mint.as_ref().map(|mint| mint.key()),
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        
+        validate_stake_amount(bet_amount, token.is_some(), ctx.accounts.stake_mint.as_deref(), global_config)?;
         require!(lobby_id.len() <= 64, GameError::LobbyIdTooLong);
         require!(!lobby_id.is_empty(), GameError::LobbyIdTooLong);
         // Validate lobby_id contains only safe ASCII alphanumeric characters and common symbols
@@ -29,6 +597,9 @@ pub mod snake_game {
             GameError::InvalidLobbyId
         );
         
+        // Both timeout windows must sit within the globally configured bounds
+        global_config.check_timeouts(join_timeout_seconds, play_timeout_seconds)?;
+        
         let creator_key = ctx.accounts.creator.key();
         
         // Prevent self-referrals
@@ -39,71 +610,178 @@ pub mod snake_game {
         let lobby = &mut ctx.accounts.lobby;
         let clock = Clock::get()?;
         
+        lobby.version = LOBBY_VERSION;
         lobby.id = lobby_id;
         lobby.creator = creator_key;
+        lobby.max_players = max_players;
+        lobby.payout_bps = payout_bps;
+        lobby.team_mode = team_mode;
+        lobby.winning_team = None;
+        lobby.invite = invite.clone();
+        lobby.series_length = series_length;
+        lobby.rounds_played = 0;
+        lobby.bonus_amount = 0;
+        lobby.open_bonus_contributions = 0;
         lobby.bet_amount = bet_amount;
+        lobby.mint = token.as_ref().map(|token| token.mint.key());
         lobby.status = LobbyStatus::Waiting;
         lobby.created_at = clock.unix_timestamp;
-        lobby.opponent = None;
         lobby.winner = None;
+        lobby.forfeited_by = None;
         lobby.referrer = referrer;
-        lobby.creator_claimed_draw = None;
-        lobby.opponent_claimed_draw = None;
         lobby.commission_taken_draw = false;
+        
+        // Snapshot commission rates so later config changes never affect this lobby
+        lobby.commission_bps = global_config.commission_bps;
+        lobby.referrer_share_bps = global_config.referrer_share_bps;
+        lobby.join_timeout_seconds = join_timeout_seconds;
+        lobby.play_timeout_seconds = play_timeout_seconds;
 
-        // Vault is now created automatically by Anchor with init attribute
+        // Vault (and token vault for SPL lobbies) is now created automatically by Anchor with init attribute
 
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.creator.to_account_info(),
-                to: ctx.accounts.vault.to_account_info(),
-            },
-        );
-        anchor_lang::system_program::transfer(cpi_context, bet_amount)?;
+        let escrowed = transfer_to_vault(
+            &ctx.accounts.creator,
+            &ctx.accounts.vault,
+            token.as_ref(),
+            &ctx.accounts.creator_token_account,
+            &ctx.accounts.system_program,
+            bet_amount,
+        )?;
+        require!(escrowed > 0, GameError::BetAmountTooSmall);
+        
+        // Creator is always the first player, and on the first team in team lobbies
+        lobby.players = vec![LobbyPlayer {
+            player: creator_key,
+            team: 0,
+            wins: 0,
+            escrowed,
+            claimed: false,
+        }];
         
         emit!(LobbyCreated {
             lobby_id: lobby.id.clone(),
             creator: lobby.creator,
             bet_amount: lobby.bet_amount,
+            escrowed_amount: escrowed,
+            max_players,
+            payout_bps: lobby.payout_bps.clone(),
+            team_mode,
+            invite,
+            series_length,
+            mint: lobby.mint,
+            join_timeout_seconds,
+            play_timeout_seconds,
             timestamp: clock.unix_timestamp,
         });
         
         Ok(())
     }
 
-    pub fn join_lobby(ctx: Context<JoinLobby>) -> Result<()> {
+    pub fn join_lobby(ctx: Context<JoinLobby>, team: Option<u8>) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_JOIN_LOBBY), GameError::InstructionPaused);
+        
         let lobby = &mut ctx.accounts.lobby;
         let clock = Clock::get()?;
         
+        let player = ctx.accounts.opponent.key();
+        
         require!(lobby.status == LobbyStatus::Waiting, GameError::LobbyNotAvailable);
-        require!(lobby.opponent.is_none(), GameError::LobbyFull);
-        require!(ctx.accounts.opponent.key() != lobby.creator, GameError::CannotJoinOwnLobby);
+        require!(lobby.players.len() < lobby.max_players as usize, GameError::LobbyFull);
+        require!(player != lobby.creator, GameError::CannotJoinOwnLobby);
+        require!(!lobby.is_player(&player), GameError::AlreadyJoined);
         
-        lobby.opponent = Some(ctx.accounts.opponent.key());
-        lobby.status = LobbyStatus::InProgress;
-        lobby.game_started_at = Some(clock.unix_timestamp);
+        // Private lobbies only admit the invited opponent, or a joiner the invite key signed for. The signature
+        // covers the joiner's address, so it can't be lifted from a pending transaction and reused by someone else.
+        match &lobby.invite {
+            Some(LobbyInvite::Opponent(opponent)) => {
+                require!(player == *opponent, GameError::NotInvited);
+            },
+            Some(LobbyInvite::InviteKey(invite_key)) => {
+                let message = lobby.invite_message(&player);
+                verify_invite_signature(&ctx.accounts.instruction_sysvar, invite_key, message.as_bytes())?;
+            },
+            None => {},
+        }
         
-        // Transfer bet from opponent to vault
-        let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.opponent.to_account_info(),
-                to: ctx.accounts.vault.to_account_info(),
+        // Team lobbies require picking a team with a free slot, other lobbies take no team
+        let team = match (lobby.team_mode, team) {
+            (true, Some(team)) => {
+                require!(team < TEAM_COUNT, GameError::InvalidTeam);
+                require!(lobby.team_members(team).count() < TEAM_SIZE as usize, GameError::TeamFull);
+                team
             },
-        );
-        anchor_lang::system_program::transfer(cpi_context, lobby.bet_amount)?;
+            (false, None) => 0,
+            _ => return Err(GameError::InvalidTeam.into()),
+        };
+        
+        // Transfer bet from the joining player to vault in the lobby's asset
+        let token = vault_token(
+            lobby.mint,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        let escrowed = transfer_to_vault(
+            &ctx.accounts.opponent,
+            &ctx.accounts.vault,
+            token.as_ref(),
+            &ctx.accounts.opponent_token_account,
+            &ctx.accounts.system_program,
+            lobby.bet_amount,
+        )?;
+        require!(escrowed > 0, GameError::BetAmountTooSmall);
+        
+        lobby.players.push(LobbyPlayer {
+            player,
+            team,
+            wins: 0,
+            escrowed,
+            claimed: false,
+        });
         
         emit!(PlayerJoined {
             lobby_id: lobby.id.clone(),
-            opponent: ctx.accounts.opponent.key(),
+            player,
+            escrowed_amount: escrowed,
             timestamp: clock.unix_timestamp,
         });
         
+        if lobby.team_mode {
+            emit!(TeamJoined {
+                lobby_id: lobby.id.clone(),
+                player,
+                team,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+        
+        // Game starts automatically once the lobby is full
+        if lobby.players.len() == lobby.max_players as usize {
+            lobby.start(clock.unix_timestamp);
+        }
+        
+        Ok(())
+    }
+
+    pub fn start_lobby(ctx: Context<StartLobby>) -> Result<()> {
+        let lobby = &mut ctx.accounts.lobby;
+        
+        // Creator may start early once enough players have joined
+        require!(lobby.status == LobbyStatus::Waiting, GameError::LobbyNotAvailable);
+        require!(lobby.players.len() >= MIN_PLAYERS as usize, GameError::NotEnoughPlayers);
+        // Every paid place needs a player to fill it
+        require!(lobby.players.len() >= lobby.payout_bps.len(), GameError::NotEnoughPlayers);
+        // Team lobbies only start on their own once both teams are complete
+        require!(!lobby.team_mode, GameError::InvalidTeamSize);
+        
+        lobby.start(Clock::get()?.unix_timestamp);
+        
         Ok(())
     }
 
-    pub fn claim_prize(ctx: Context<ClaimPrize>, game_signature: Vec<u8>, nonce: u64) -> Result<()> {
+    pub fn claim_prize(ctx: Context<ClaimPrize>, game_signatures: Vec<[u8; 64]>, nonce: u64) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_CLAIM_PRIZE), GameError::InstructionPaused);
+        
         let lobby = &mut ctx.accounts.lobby;
         let clock = Clock::get()?;
         let winner = ctx.accounts.winner.key();
@@ -111,104 +789,72 @@ pub mod snake_game {
         // Critical: Ensure lobby is in progress and hasn't been claimed yet
         require!(lobby.status == LobbyStatus::InProgress, GameError::GameNotInProgress);
         require!(lobby.winner.is_none(), GameError::PrizeAlreadyClaimed);
+        // Lobbies with a payout table settle through settle_ranked, team lobbies through settle_team
+        require!(lobby.payout_bps.is_empty(), GameError::RankedSettlementRequired);
+        require!(!lobby.team_mode, GameError::TeamSettlementRequired);
+        // Series lobbies pay out once a player reaches the required wins through record_round
+        require!(lobby.series_length <= 1, GameError::SeriesSettlementRequired);
         
         // Validate winner is a legitimate participant
-        require!(winner == lobby.creator || Some(winner) == lobby.opponent, GameError::InvalidWinner);
+        let winner_index = lobby.player_index(&winner).ok_or(GameError::InvalidWinner)?;
         
         
         // Verify the winner account is actually signing this transaction
         require!(ctx.accounts.winner.is_signer, GameError::WinnerMustSign);
         
         // Prevent replay attacks by including nonce in signature
-        let message = format!("game:{}:{}:{}", lobby.id, winner.to_string(), nonce);
+        let message = format!("game:{}:{}:{}", lobby.id, winner, nonce);
         let message_bytes = message.as_bytes();
         
-        // Extract signature from game_signature (should be 64 bytes)
-        require!(game_signature.len() == 64, GameError::InvalidSignature);
-        
-        let backend_pubkey_bytes = BACKEND_AUTHORITY.to_bytes();
-        
-        // Verify ed25519 signature using instruction sysvar
-        verify_ed25519_signature(
+        // Verify at least oracle_threshold registered oracles signed the message using instruction sysvar
+        verify_oracle_signatures(
             &ctx.accounts.instruction_sysvar,
-            &backend_pubkey_bytes,
+            &ctx.accounts.global_config,
             message_bytes,
-            &game_signature,
+            &game_signatures,
         )?;
         
         // Atomically update lobby state to prevent race conditions
         lobby.winner = Some(winner);
+        lobby.players[winner_index].claimed = true;
         lobby.status = LobbyStatus::Completed;
         lobby.completed_at = Some(clock.unix_timestamp);
         
-        // Calculate total prize pool (2x bet amount)
-        let total_pool = lobby.bet_amount.checked_mul(2).ok_or(GameError::ArithmeticOverflow)?;
-        
-        // Calculate 5% total commission (2.5% for us, 2.5% for referrer if exists)
-        let total_commission = total_pool.checked_mul(5).ok_or(GameError::ArithmeticOverflow)?
-            .checked_div(100).ok_or(GameError::ArithmeticOverflow)?;
-        
-        let (our_commission, referrer_commission) = if lobby.referrer.is_some() {
-            // If referrer exists, split 5% equally: 2.5% each
-            let half_commission = total_commission.checked_div(2).ok_or(GameError::ArithmeticOverflow)?;
-            let remainder = total_commission.checked_sub(half_commission.checked_mul(2).ok_or(GameError::ArithmeticOverflow)?).ok_or(GameError::ArithmeticOverflow)?;
-            // Give remainder to us (contract) to handle rounding
-            (half_commission.checked_add(remainder).ok_or(GameError::ArithmeticOverflow)?, half_commission)
-        } else {
-            // If no referrer, we get full 5%
-            (total_commission, 0)
+        let lobby_key = lobby.key();
+        let vault_bump = [ctx.bumps.vault];
+        let vault_seeds: &[&[u8]] = &[b"vault", lobby_key.as_ref(), &vault_bump];
+        let lobby_vault = LobbyVault {
+            vault: &ctx.accounts.vault,
+            signer_seeds: vault_seeds,
+            token: vault_token(lobby.mint, &ctx.accounts.mint, &ctx.accounts.vault_token_account, &ctx.accounts.token_program)?,
         };
         
-        let prize_after_commission = total_pool.checked_sub(total_commission).ok_or(GameError::ArithmeticOverflow)?;
-        
-        // Store our commission amount in contract state for tracking
-        let contract_state = &mut ctx.accounts.contract_state;
-        contract_state.accumulated_commission = contract_state.accumulated_commission.checked_add(our_commission).ok_or(GameError::ArithmeticOverflow)?;
-        
-        // Validate vault has sufficient balance before transfers (including rent-exempt amount)
-        let vault_balance = ctx.accounts.vault.lamports();
+        // Prize pool is what the vault actually holds (excluding rent-exempt amount)
+        let total_pool = lobby_vault.stake_balance()?;
         let rent_exempt_amount = Rent::get()?.minimum_balance(0);
-        require!(vault_balance >= total_pool + rent_exempt_amount, GameError::InsufficientVaultBalance);
         
+        // Commission is taken at the rate snapshotted when the lobby was created, the winner takes the rest
+        let (total_commission, prizes) = lobby.settlement(total_pool, &[BPS_DENOMINATOR])?;
+        let prize_after_commission = prizes[0];
         
-        // Transfer our commission to commission vault using safe methods
-        ctx.accounts.vault.sub_lamports(our_commission)?;
-        ctx.accounts.commission_vault.add_lamports(our_commission)?;
+        // Transfer our commission to commission vault, and referrer commission if the referrer can receive it
+        let credited_commission = take_commission(
+            &lobby_vault,
+            total_commission,
+            lobby.referrer_share_bps,
+            lobby.referrer,
+            &ctx.accounts.commission_vault,
+            &ctx.accounts.commission_token_vault,
+            &ctx.accounts.referrer,
+            &ctx.accounts.referrer_token_account,
+        )?;
         
-        // Transfer referrer commission if referrer exists and account provided
-        if let Some(referrer_key) = lobby.referrer {
-            if let Some(referrer_account) = &ctx.accounts.referrer {
-                // Validate referrer account matches the one stored in lobby
-                require!(referrer_account.key() == referrer_key, GameError::InvalidReferrer);
-                
-                // Check if referrer account has sufficient balance to remain rent-exempt after receiving commission
-                let current_balance = referrer_account.lamports();
-                let rent_exempt_minimum = Rent::get()?.minimum_balance(0);
-                
-                // Only transfer if referrer account can safely receive funds
-                // If account doesn't exist or has insufficient rent, add commission to our vault instead
-                if current_balance > 0 || referrer_commission >= rent_exempt_minimum {
-                    ctx.accounts.vault.sub_lamports(referrer_commission)?;
-                    referrer_account.add_lamports(referrer_commission)?;
-                } else {
-                    // Add referrer's commission to our commission (safer fallback)
-                    contract_state.accumulated_commission = contract_state.accumulated_commission
-                        .checked_add(referrer_commission).ok_or(GameError::ArithmeticOverflow)?;
-                    ctx.accounts.vault.sub_lamports(referrer_commission)?;
-                    ctx.accounts.commission_vault.add_lamports(referrer_commission)?;
-                }
-            } else {
-                // If referrer account not provided, add referrer's commission to our commission
-                contract_state.accumulated_commission = contract_state.accumulated_commission
-                    .checked_add(referrer_commission).ok_or(GameError::ArithmeticOverflow)?;
-                ctx.accounts.vault.sub_lamports(referrer_commission)?;
-                ctx.accounts.commission_vault.add_lamports(referrer_commission)?;
-            }
-        }
+        // Store our commission amount in contract state for tracking, per asset
+        ctx.accounts.contract_state.credit_commission(lobby.mint, credited_commission)?;
         
         // Transfer prize but keep rent-exempt amount in vault
-        ctx.accounts.vault.sub_lamports(prize_after_commission)?;
-        ctx.accounts.winner.add_lamports(prize_after_commission)?;
+        let winner_destination = lobby_vault.payout_account(&ctx.accounts.winner.to_account_info(), &ctx.accounts.winner_token_account)?;
+        lobby_vault.transfer_out(&winner_destination, prize_after_commission)?;
         
         // Ensure vault retains rent-exempt status
         let remaining_balance = ctx.accounts.vault.lamports();
@@ -224,534 +870,3596 @@ pub mod snake_game {
         Ok(())
     }
 
-    pub fn claim_commission(ctx: Context<ClaimCommission>, amount: u64) -> Result<()> {
-        let contract_state = &mut ctx.accounts.contract_state;
+    pub fn settle_ranked<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleLobby<'info>>,
+        ranking: Vec<Pubkey>,
+        game_signatures: Vec<[u8; 64]>,
+        nonce: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_CLAIM_PRIZE), GameError::InstructionPaused);
         
-        require!(contract_state.accumulated_commission >= amount, GameError::InsufficientCommission);
+        let lobby = &ctx.accounts.lobby;
+        let clock = Clock::get()?;
         
-        // Validate commission vault has sufficient balance
-        let vault_balance = ctx.accounts.commission_vault.lamports();
-        require!(vault_balance >= amount, GameError::InsufficientVaultBalance);
+        // Ensure lobby is in progress and hasn't been settled yet
+        require!(lobby.status == LobbyStatus::InProgress, GameError::GameNotInProgress);
+        require!(lobby.winner.is_none(), GameError::PrizeAlreadyClaimed);
+        require!(!lobby.team_mode, GameError::TeamSettlementRequired);
+        require!(lobby.series_length <= 1, GameError::SeriesSettlementRequired);
         
-        contract_state.accumulated_commission = contract_state.accumulated_commission.checked_sub(amount).ok_or(GameError::ArithmeticOverflow)?;
+        // Any participant may submit the attested ranking, payouts only ever go to placed players
+        require!(lobby.is_player(&ctx.accounts.settler.key()), GameError::InvalidClaimer);
         
-        // Transfer from commission vault to claimer using safe methods
-        // Keep rent-exempt amount in commission vault
-        let rent_exempt_amount = Rent::get()?.minimum_balance(0);
-        let remaining_balance = vault_balance.checked_sub(amount).ok_or(GameError::ArithmeticOverflow)?;
-        require!(remaining_balance >= rent_exempt_amount, GameError::InsufficientVaultBalance);
+        // Ranking lists one distinct player per paid place, winner first
+        let payout_table = lobby.payout_table();
+        require!(ranking.len() == payout_table.len(), GameError::InvalidRanking);
+        for (place, player) in ranking.iter().enumerate() {
+            require!(lobby.is_player(player), GameError::InvalidRanking);
+            require!(!ranking[..place].contains(player), GameError::InvalidRanking);
+        }
         
-        ctx.accounts.commission_vault.sub_lamports(amount)?;
-        ctx.accounts.commission_claimer.add_lamports(amount)?;
+        // Prevent replay attacks by including nonce in signature
+        let ranked_players = ranking.iter().map(|player| player.to_string()).collect::<Vec<_>>().join(",");
+        let message = format!("ranking:{}:{}:{}", lobby.id, ranked_players, nonce);
         
-        emit!(CommissionClaimed {
-            claimer: ctx.accounts.commission_claimer.key(),
-            amount,
-            timestamp: Clock::get()?.unix_timestamp,
+        // Verify at least oracle_threshold registered oracles signed the message using instruction sysvar
+        verify_oracle_signatures(
+            &ctx.accounts.instruction_sysvar,
+            &ctx.accounts.global_config,
+            message.as_bytes(),
+            &game_signatures,
+        )?;
+        
+        // Payout destinations are passed in ranking order
+        let prizes = settle_pool(ctx.accounts, ctx.remaining_accounts, ctx.bumps.vault, &ranking, &payout_table)?;
+        
+        emit!(RankedGameCompleted {
+            lobby_id: ctx.accounts.lobby.id.clone(),
+            ranking,
+            prizes,
+            timestamp: clock.unix_timestamp,
         });
         
         Ok(())
     }
 
+    pub fn settle_team<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleLobby<'info>>,
+        winning_team: u8,
+        game_signatures: Vec<[u8; 64]>,
+        nonce: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_CLAIM_PRIZE), GameError::InstructionPaused);
+        
+        let lobby = &ctx.accounts.lobby;
+        let clock = Clock::get()?;
+        
+        // Ensure lobby is an in-progress team lobby that hasn't been settled yet
+        require!(lobby.team_mode, GameError::NotTeamLobby);
+        require!(lobby.status == LobbyStatus::InProgress, GameError::GameNotInProgress);
+        require!(lobby.winner.is_none(), GameError::PrizeAlreadyClaimed);
+        require!(winning_team < TEAM_COUNT, GameError::InvalidTeam);
+        
+        // Any participant may submit the attested result, payouts only ever go to the winning team
+        require!(lobby.is_player(&ctx.accounts.settler.key()), GameError::InvalidClaimer);
+        
+        // Prevent replay attacks by including nonce in signature
+        let message = format!("team:{}:{}:{}", lobby.id, winning_team, nonce);
+        
+        // Verify at least oracle_threshold registered oracles signed the message using instruction sysvar
+        verify_oracle_signatures(
+            &ctx.accounts.instruction_sysvar,
+            &ctx.accounts.global_config,
+            message.as_bytes(),
+            &game_signatures,
+        )?;
+        
+        // Winning team members share the prize equally, in join order
+        let (winners, shares_bps) = lobby.team_payouts(winning_team);
+        
+        // Payout destinations are passed in the same order as the winning team members
+        ctx.accounts.lobby.winning_team = Some(winning_team);
+        let prizes = settle_pool(ctx.accounts, ctx.remaining_accounts, ctx.bumps.vault, &winners, &shares_bps)?;
+        
+        emit!(TeamGameCompleted {
+            lobby_id: ctx.accounts.lobby.id.clone(),
+            winning_team,
+            winners,
+            prizes,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
 
-    pub fn claim_draw_refund(ctx: Context<ClaimDrawRefund>, game_signature: Vec<u8>, nonce: u64) -> Result<()> {
+    pub fn record_round<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleLobby<'info>>,
+        round: u8,
+        round_winner: Option<Pubkey>,
+        game_signatures: Vec<[u8; 64]>,
+        nonce: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_CLAIM_PRIZE), GameError::InstructionPaused);
+        
         let lobby = &mut ctx.accounts.lobby;
         let clock = Clock::get()?;
-        let claimer = ctx.accounts.claimer.key();
         
-        // Ensure lobby is in progress or already marked as draw
+        // Ensure lobby is an in-progress series that hasn't been decided yet
+        require!(lobby.series_length > 1, GameError::NotSeriesLobby);
+        require!(lobby.status == LobbyStatus::InProgress, GameError::GameNotInProgress);
+        require!(lobby.winner.is_none(), GameError::PrizeAlreadyClaimed);
+        
+        // Rounds are recorded strictly in order, so an attested result can't be applied twice
+        require!(round == lobby.rounds_played.checked_add(1).ok_or(GameError::ArithmeticOverflow)?, GameError::InvalidRound);
+        
+        // Any participant may submit the attested result
+        require!(lobby.is_player(&ctx.accounts.settler.key()), GameError::InvalidClaimer);
+        
+        // Prevent replay attacks by including nonce in signature, a drawn round is attested as "draw"
+        let outcome = round_winner.map_or("draw".to_string(), |winner| winner.to_string());
+        let message = format!("round:{}:{}:{}:{}", lobby.id, round, outcome, nonce);
+        
+        // Verify at least oracle_threshold registered oracles signed the message using instruction sysvar
+        verify_oracle_signatures(
+            &ctx.accounts.instruction_sysvar,
+            &ctx.accounts.global_config,
+            message.as_bytes(),
+            &game_signatures,
+        )?;
+        
+        let series_winner = lobby.apply_round(round_winner, clock.unix_timestamp)?;
+        
+        emit!(RoundRecorded {
+            lobby_id: lobby.id.clone(),
+            round,
+            winner: round_winner,
+            wins: lobby.players.iter().map(|entry| entry.wins).collect(),
+            timestamp: clock.unix_timestamp,
+        });
+        
+        if let Some(series_winner) = series_winner {
+            // Payout destination for the series winner is the only remaining account
+            let prizes = settle_pool(ctx.accounts, ctx.remaining_accounts, ctx.bumps.vault, &[series_winner], &[BPS_DENOMINATOR])?;
+            
+            emit!(GameCompleted {
+                lobby_id: ctx.accounts.lobby.id.clone(),
+                winner: series_winner,
+                prize: prizes[0],
+                timestamp: clock.unix_timestamp,
+            });
+        }
+        
+        Ok(())
+    }
+
+    pub fn forfeit<'info>(ctx: Context<'_, '_, '_, 'info, SettleLobby<'info>>) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_CLAIM_PRIZE), GameError::InstructionPaused);
+        
+        let lobby = &ctx.accounts.lobby;
+        let clock = Clock::get()?;
+        let forfeiter = ctx.accounts.settler.key();
+        
+        require!(lobby.status == LobbyStatus::InProgress, GameError::GameNotInProgress);
+        require!(lobby.winner.is_none(), GameError::PrizeAlreadyClaimed);
+        
+        // Conceding hands the whole pot to the single other participant, so only 1v1 lobbies can be forfeited
         require!(
-            lobby.status == LobbyStatus::InProgress || lobby.status == LobbyStatus::Draw, 
-            GameError::GameNotInProgress
+            lobby.players.len() == MIN_PLAYERS as usize && lobby.payout_bps.is_empty() && !lobby.team_mode,
+            GameError::ForfeitUnsupported
         );
+        let forfeiter_index = lobby.player_index(&forfeiter).ok_or(GameError::InvalidClaimer)?;
+        let winner = lobby.players[1 - forfeiter_index].player;
         
-        // Validate claimer is a legitimate participant
-        require!(claimer == lobby.creator || Some(claimer) == lobby.opponent, GameError::InvalidClaimer);
+        // Recorded so a spectator pool on this game is voided instead of paying out on a result
+        // the forfeiting player chose alone
+        ctx.accounts.lobby.forfeited_by = Some(forfeiter);
         
-        // Ensure both participants exist (can't have draw without opponent)
-        require!(lobby.opponent.is_some(), GameError::OpponentNotFound);
+        // The forfeiting player's own signature is the result, no backend attestation needed.
+        // Payout destination for the other participant is the only remaining account.
+        let prizes = settle_pool(ctx.accounts, ctx.remaining_accounts, ctx.bumps.vault, &[winner], &[BPS_DENOMINATOR])?;
         
-        // Check if claimer has already claimed their refund
-        if claimer == lobby.creator {
-            require!(lobby.creator_claimed_draw.is_none(), GameError::RefundAlreadyClaimed);
-        } else {
-            require!(lobby.opponent_claimed_draw.is_none(), GameError::RefundAlreadyClaimed);
-        }
+        emit!(GameForfeited {
+            lobby_id: ctx.accounts.lobby.id.clone(),
+            forfeited_by: forfeiter,
+            winner,
+            timestamp: clock.unix_timestamp,
+        });
         
-        // Verify the claimer account is actually signing this transaction
-        require!(ctx.accounts.claimer.is_signer, GameError::ClaimerMustSign);
+        emit!(GameCompleted {
+            lobby_id: ctx.accounts.lobby.id.clone(),
+            winner,
+            prize: prizes[0],
+            timestamp: clock.unix_timestamp,
+        });
         
-        // Prevent replay attacks by including nonce in signature
-        let message = format!("draw:{}:{}:{}", lobby.id, claimer.to_string(), nonce);
-        let message_bytes = message.as_bytes();
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_tournament(
+        ctx: Context<CreateTournament>,
+        tournament_id: String,
+        entry_fee: u64,
+        max_entrants: u8,
+        payout_bps: Vec<u16>,
+        referrer: Option<Pubkey>,
+        registration_timeout_seconds: i64,
+        match_timeout_seconds: i64,
+    ) -> Result<()> {
+        let global_config = &ctx.accounts.global_config;
+        require!(!global_config.is_paused(PAUSE_CREATE_LOBBY), GameError::InstructionPaused);
+        
+        // Single elimination needs a full bracket
+        require!(
+            (MIN_TOURNAMENT_ENTRANTS..=MAX_TOURNAMENT_ENTRANTS).contains(&max_entrants) && max_entrants.is_power_of_two(),
+            GameError::InvalidTournamentSize
+        );
+        require!(!payout_bps.is_empty(), GameError::InvalidPayoutTable);
+        validate_payout_table(&payout_bps, max_entrants)?;
         
-        // Extract signature from game_signature (should be 64 bytes)
-        require!(game_signature.len() == 64, GameError::InvalidSignature);
+        require!(tournament_id.len() <= 64, GameError::LobbyIdTooLong);
+        require!(!tournament_id.is_empty(), GameError::LobbyIdTooLong);
+        require!(
+            tournament_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+            GameError::InvalidLobbyId
+        );
         
-        let backend_pubkey_bytes = BACKEND_AUTHORITY.to_bytes();
+        global_config.check_timeouts(registration_timeout_seconds, match_timeout_seconds)?;
         
-        // Verify ed25519 signature using instruction sysvar
-        verify_ed25519_signature(
-            &ctx.accounts.instruction_sysvar,
-            &backend_pubkey_bytes,
-            message_bytes,
-            &game_signature,
+        // Passing a mint makes entry fees SPL tokens, otherwise they are in SOL
+        let token = vault_token(
+            ctx.accounts.mint.as_ref().map(|mint| mint.key()),
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program,
         )?;
+        // Entry fees are held to the same limits as lobby stakes
+        validate_stake_amount(entry_fee, token.is_some(), ctx.accounts.stake_mint.as_deref(), global_config)?;
+        
+        let tournament = &mut ctx.accounts.tournament;
+        let clock = Clock::get()?;
         
-        // Calculate total prize pool (2x bet amount)
-        let total_pool = lobby.bet_amount.checked_mul(2).ok_or(GameError::ArithmeticOverflow)?;
+        tournament.id = tournament_id;
+        tournament.organizer = ctx.accounts.admin.key();
+        tournament.entry_fee = entry_fee;
+        tournament.mint = token.as_ref().map(|token| token.mint.key());
+        tournament.max_entrants = max_entrants;
+        tournament.entrants = Vec::new();
+        tournament.payout_bps = payout_bps;
+        tournament.match_winners = Vec::new();
+        tournament.round = 0;
+        tournament.status = TournamentStatus::Registration;
+        tournament.referrer = referrer;
         
-        // Calculate 5% total commission (2.5% for us, 2.5% for referrer if exists)
-        let total_commission = total_pool.checked_mul(5).ok_or(GameError::ArithmeticOverflow)?
-            .checked_div(100).ok_or(GameError::ArithmeticOverflow)?;
+        // Snapshot commission rates so later config changes never affect this tournament
+        tournament.commission_bps = global_config.commission_bps;
+        tournament.referrer_share_bps = global_config.referrer_share_bps;
+        tournament.registration_timeout_seconds = registration_timeout_seconds;
+        tournament.match_timeout_seconds = match_timeout_seconds;
+        tournament.created_at = clock.unix_timestamp;
+        tournament.started_at = None;
+        tournament.round_started_at = None;
+        tournament.completed_at = None;
+        tournament.bump = ctx.bumps.tournament;
+        
+        emit!(TournamentCreated {
+            tournament_id: tournament.id.clone(),
+            organizer: tournament.organizer,
+            entry_fee,
+            mint: tournament.mint,
+            max_entrants,
+            payout_bps: tournament.payout_bps.clone(),
+            referrer,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
 
-        let (our_commission, referrer_commission, commission_per_player) = 
-            if !lobby.commission_taken_draw {
-                // Commission not taken yet, calculate and take it
-                // Calculate remainder to handle odd total_commission correctly
-                let remainder = total_commission.checked_rem(2).ok_or(GameError::ArithmeticOverflow)?;
-                let half_commission = total_commission.checked_div(2).ok_or(GameError::ArithmeticOverflow)?;
-                
-                let (our_comm, ref_comm) = if lobby.referrer.is_some() {
-                    // If referrer exists, split 5% equally: 2.5% each
-                    // Don't add remainder to our_commission to avoid rent-exempt issues
-                    (half_commission, half_commission)
-                } else {
-                    // If no referrer, we get total commission minus remainder to keep vault rent-exempt
-                    (total_commission.checked_sub(remainder).ok_or(GameError::ArithmeticOverflow)?, 0)
-                };
-                
-                // Calculate commission per player using ceiling division to include remainder
-                let commission_per_player = total_commission.checked_add(1).ok_or(GameError::ArithmeticOverflow)?
-                    .checked_div(2).ok_or(GameError::ArithmeticOverflow)?;
-                (our_comm, ref_comm, commission_per_player)
-            } else {
-                // Commission already taken, no commission for this call
-                // Use ceiling division to match the original calculation
-                let commission_per_player = total_commission.checked_add(1).ok_or(GameError::ArithmeticOverflow)?
-                    .checked_div(2).ok_or(GameError::ArithmeticOverflow)?;
-                (0, 0, commission_per_player)
-            };
+    pub fn join_tournament(ctx: Context<JoinTournament>) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_JOIN_LOBBY), GameError::InstructionPaused);
         
-        let refund_amount = lobby.bet_amount.checked_sub(commission_per_player).ok_or(GameError::ArithmeticOverflow)?;
+        let tournament = &mut ctx.accounts.tournament;
+        let clock = Clock::get()?;
+        let player = ctx.accounts.entrant.key();
         
-        // Handle commission transfers only if commission hasn't been taken yet
-        if !lobby.commission_taken_draw {
-            // Store our commission amount in contract state for tracking
-            let contract_state = &mut ctx.accounts.contract_state;
-            contract_state.accumulated_commission = contract_state.accumulated_commission.checked_add(our_commission).ok_or(GameError::ArithmeticOverflow)?;
-            
-            // Transfer our commission to commission vault
-            ctx.accounts.vault.sub_lamports(our_commission)?;
-            ctx.accounts.commission_vault.add_lamports(our_commission)?;
-            
-            // Transfer referrer commission if referrer exists and account provided
-            if let Some(referrer_key) = lobby.referrer {
-                if let Some(referrer_account) = &ctx.accounts.referrer {
-                    // Validate referrer account matches the one stored in lobby
-                    require!(referrer_account.key() == referrer_key, GameError::InvalidReferrer);
-                    
-                    // Check if referrer account has sufficient balance to remain rent-exempt after receiving commission
-                    let current_balance = referrer_account.lamports();
-                    let rent_exempt_minimum = Rent::get()?.minimum_balance(0);
-                    
-                    // Only transfer if referrer account can safely receive funds
-                    // If account doesn't exist or has insufficient rent, add commission to our vault instead
-                    if current_balance > 0 || referrer_commission >= rent_exempt_minimum {
-                        ctx.accounts.vault.sub_lamports(referrer_commission)?;
-                        referrer_account.add_lamports(referrer_commission)?;
-                    } else {
-                        // Add referrer's commission to our commission (safer fallback)
-                        contract_state.accumulated_commission = contract_state.accumulated_commission
-                            .checked_add(referrer_commission).ok_or(GameError::ArithmeticOverflow)?;
-                        ctx.accounts.vault.sub_lamports(referrer_commission)?;
-                        ctx.accounts.commission_vault.add_lamports(referrer_commission)?;
-                    }
-                } else {
-                    // If referrer account not provided, add referrer's commission to our commission
-                    contract_state.accumulated_commission = contract_state.accumulated_commission
-                        .checked_add(referrer_commission).ok_or(GameError::ArithmeticOverflow)?;
-                    ctx.accounts.vault.sub_lamports(referrer_commission)?;
-                    ctx.accounts.commission_vault.add_lamports(referrer_commission)?;
-                }
-            }
-            
-            // Mark commission as taken
-            lobby.commission_taken_draw = true;
+        require!(tournament.status == TournamentStatus::Registration, GameError::TournamentNotOpen);
+        let registration_deadline = tournament.created_at.checked_add(tournament.registration_timeout_seconds)
+            .ok_or(GameError::ArithmeticOverflow)?;
+        require!(clock.unix_timestamp < registration_deadline, GameError::TournamentNotOpen);
+        require!(tournament.entrants.len() < tournament.max_entrants as usize, GameError::TournamentFull);
+        require!(tournament.entrant_index(&player).is_none(), GameError::AlreadyJoined);
+        
+        // Transfer the entry fee to the tournament vault in the tournament's asset
+        let token = vault_token(
+            tournament.mint,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        let escrowed = transfer_to_vault(
+            &ctx.accounts.entrant,
+            &ctx.accounts.vault,
+            token.as_ref(),
+            &ctx.accounts.entrant_token_account,
+            &ctx.accounts.system_program,
+            tournament.entry_fee,
+        )?;
+        require!(escrowed > 0, GameError::BetAmountTooSmall);
+        
+        tournament.entrants.push(TournamentEntrant {
+            player,
+            escrowed,
+            eliminated_in: None,
+        });
+        
+        emit!(TournamentJoined {
+            tournament_id: tournament.id.clone(),
+            player,
+            escrowed_amount: escrowed,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        // Bracket is seeded in join order once every slot is taken
+        if tournament.entrants.len() == tournament.max_entrants as usize {
+            tournament.status = TournamentStatus::InProgress;
+            tournament.started_at = Some(clock.unix_timestamp);
+            tournament.start_round(1, clock.unix_timestamp);
         }
         
-        // Validate vault has sufficient balance before transfers (including rent-exempt amount)
-        let vault_balance = ctx.accounts.vault.lamports();
-        let rent_exempt_amount = Rent::get()?.minimum_balance(0);
-        require!(vault_balance >= refund_amount + rent_exempt_amount, GameError::InsufficientVaultBalance);
+        Ok(())
+    }
+
+    pub fn record_tournament_match<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleTournament<'info>>,
+        round: u8,
+        match_index: u8,
+        match_winner: Pubkey,
+        game_signatures: Vec<[u8; 64]>,
+        nonce: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_CLAIM_PRIZE), GameError::InstructionPaused);
         
-        // Transfer refund to claimer
-        ctx.accounts.vault.sub_lamports(refund_amount)?;
-        ctx.accounts.claimer.add_lamports(refund_amount)?;
+        let tournament = &mut ctx.accounts.tournament;
+        let clock = Clock::get()?;
         
-        // Mark this participant as having claimed their refund and set status to Draw if needed
-        if claimer == lobby.creator {
-            lobby.creator_claimed_draw = Some(true);
-        } else {
-            lobby.opponent_claimed_draw = Some(true);
+        require!(tournament.status == TournamentStatus::InProgress, GameError::TournamentNotInProgress);
+        require!(tournament.entrant_index(&ctx.accounts.settler.key()).is_some(), GameError::InvalidClaimer);
+        
+        // Matches of the current round can be recorded in any order, each only once
+        require!(round == tournament.round, GameError::InvalidMatch);
+        let match_slot = tournament.match_winners.get(match_index as usize).ok_or(GameError::InvalidMatch)?;
+        require!(match_slot.is_none(), GameError::MatchAlreadyRecorded);
+        
+        let round_players = tournament.round_players();
+        let pairing = [round_players[match_index as usize * 2], round_players[match_index as usize * 2 + 1]];
+        require!(pairing.contains(&match_winner), GameError::InvalidWinner);
+        let match_loser = if pairing[0] == match_winner { pairing[1] } else { pairing[0] };
+        
+        // Prevent replay attacks by including nonce in signature
+        let message = format!("tournament:{}:{}:{}:{}:{}", tournament.id, round, match_index, match_winner, nonce);
+        
+        // Verify at least oracle_threshold registered oracles signed the message using instruction sysvar
+        verify_oracle_signatures(
+            &ctx.accounts.instruction_sysvar,
+            &ctx.accounts.global_config,
+            message.as_bytes(),
+            &game_signatures,
+        )?;
+        
+        tournament.match_winners[match_index as usize] = Some(match_winner);
+        let loser_index = tournament.entrant_index(&match_loser).ok_or(GameError::InvalidWinner)?;
+        tournament.entrants[loser_index].eliminated_in = Some(round);
+        
+        emit!(TournamentMatchRecorded {
+            tournament_id: tournament.id.clone(),
+            round,
+            match_index,
+            winner: match_winner,
+            loser: match_loser,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        // Round advances once all of its matches are recorded
+        if tournament.match_winners.iter().any(|winner| winner.is_none()) {
+            return Ok(());
+        }
+        if tournament.match_winners.len() > 1 {
+            let next_round = round.checked_add(1).ok_or(GameError::ArithmeticOverflow)?;
+            tournament.start_round(next_round, clock.unix_timestamp);
+            return Ok(());
         }
         
-        // Set lobby status to Draw and completion time if this is the first claim
-        if lobby.status == LobbyStatus::InProgress {
-            lobby.status = LobbyStatus::Draw;
-            lobby.completed_at = Some(clock.unix_timestamp);
-            
-            emit!(GameDeclaredDraw {
-                lobby_id: lobby.id.clone(),
-                timestamp: clock.unix_timestamp,
-            });
+        // Final is decided: pay the prize pool out by placement
+        tournament.status = TournamentStatus::Completed;
+        tournament.completed_at = Some(clock.unix_timestamp);
+        let placements = tournament.placement_shares();
+        
+        // Payout destinations are passed in placement order: wallets for SOL tournaments, their token accounts for SPL ones
+        require!(ctx.remaining_accounts.len() == placements.len(), GameError::InvalidPayoutAccount);
+        
+        let tournament_key = tournament.key();
+        let vault_bump = [ctx.bumps.vault];
+        let vault_seeds: &[&[u8]] = &[b"tournament_vault", tournament_key.as_ref(), &vault_bump];
+        let tournament_vault = LobbyVault {
+            vault: &ctx.accounts.vault,
+            signer_seeds: vault_seeds,
+            token: vault_token(tournament.mint, &ctx.accounts.mint, &ctx.accounts.vault_token_account, &ctx.accounts.token_program)?,
+        };
+        
+        // Prize pool is what the vault actually holds (excluding rent-exempt amount), which must cover every entry fee
+        let total_pool = tournament_vault.stake_balance()?;
+        require!(total_pool >= tournament.total_escrowed()?, GameError::InsufficientVaultBalance);
+        let rent_exempt_amount = Rent::get()?.minimum_balance(0);
+        
+        // Commission and referrer split work exactly as for lobbies, taken once from the whole pool
+        let shares_bps = placements.iter().map(|(_, share_bps)| *share_bps).collect::<Vec<_>>();
+        let (total_commission, prizes) = pool_payouts(total_pool, tournament.commission_bps, &shares_bps)?;
+        let credited_commission = take_commission(
+            &tournament_vault,
+            total_commission,
+            tournament.referrer_share_bps,
+            tournament.referrer,
+            &ctx.accounts.commission_vault,
+            &ctx.accounts.commission_token_vault,
+            &ctx.accounts.referrer,
+            &ctx.accounts.referrer_token_account,
+        )?;
+        ctx.accounts.contract_state.credit_commission(tournament.mint, credited_commission)?;
+        
+        for (((player, _), prize), destination) in placements.iter().zip(prizes.iter()).zip(ctx.remaining_accounts.iter()) {
+            let player_destination = tournament_vault.player_payout_account(*player, destination)?;
+            tournament_vault.transfer_out(&player_destination, *prize)?;
         }
         
         // Ensure vault retains rent-exempt status
-        let remaining_balance = ctx.accounts.vault.lamports();
-        require!(remaining_balance >= rent_exempt_amount, GameError::InsufficientVaultBalance);
+        require!(ctx.accounts.vault.lamports() >= rent_exempt_amount, GameError::InsufficientVaultBalance);
         
-        emit!(DrawRefundClaimed {
-            lobby_id: lobby.id.clone(),
-            claimer,
-            refund_amount,
+        emit!(TournamentCompleted {
+            tournament_id: tournament.id.clone(),
+            standings: placements.iter().map(|(player, _)| *player).collect(),
+            prizes,
             timestamp: clock.unix_timestamp,
         });
         
         Ok(())
     }
 
-
-    pub fn cancel_game_timeout(ctx: Context<CancelGameTimeout>) -> Result<()> {
-        let lobby = &mut ctx.accounts.lobby;
+    pub fn cancel_tournament<'info>(ctx: Context<'_, '_, '_, 'info, CancelTournament<'info>>) -> Result<()> {
+        let tournament = &mut ctx.accounts.tournament;
         let clock = Clock::get()?;
-        let canceller = ctx.accounts.canceller.key();
         
-        // Validate canceller is a participant in the game
-        require!(
-            canceller == lobby.creator || Some(canceller) == lobby.opponent,
-            GameError::OnlyParticipantsCanCancel
-        );
+        require!(tournament.entrant_index(&ctx.accounts.canceller.key()).is_some(), GameError::OnlyParticipantsCanCancel);
         
-        // Check timeout conditions based on lobby status
-        match lobby.status {
-            LobbyStatus::Waiting => {
-                // 60 minutes timeout from lobby creation
-                let timeout_threshold = lobby.created_at.checked_add(GAME_TIMEOUT_SECONDS)
-                    .ok_or(GameError::ArithmeticOverflow)?;
-                require!(
-                    clock.unix_timestamp >= timeout_threshold,
-                    GameError::TimeoutNotReached
-                );
-                
-                // Validate creator account matches lobby creator
-                require!(ctx.accounts.creator.key() == lobby.creator, GameError::InvalidCreator);
-                
-                // Refund only creator's bet (opponent hasn't joined yet)
-                let vault_balance = ctx.accounts.vault.lamports();
-                let rent_exempt_amount = Rent::get()?.minimum_balance(0);
-                let refund_amount = lobby.bet_amount;
-                
-                require!(vault_balance >= refund_amount + rent_exempt_amount, GameError::InsufficientVaultBalance);
-                
-                ctx.accounts.vault.sub_lamports(refund_amount)?;
-                ctx.accounts.creator.add_lamports(refund_amount)?;
-            },
-            LobbyStatus::InProgress => {
-                // 60 minutes timeout from game start
-                let game_start = lobby.game_started_at.ok_or(GameError::GameNotStarted)?;
-                let timeout_threshold = game_start.checked_add(GAME_TIMEOUT_SECONDS)
-                    .ok_or(GameError::ArithmeticOverflow)?;
-                require!(
-                    clock.unix_timestamp >= timeout_threshold,
-                    GameError::TimeoutNotReached
-                );
-                
-                // Calculate total prize pool (2x bet amount)
-                let total_pool = lobby.bet_amount.checked_mul(2).ok_or(GameError::ArithmeticOverflow)?;
-                
-                // Calculate 5% total commission (2.5% for us, 2.5% for referrer if exists)
-                let total_commission = total_pool.checked_mul(5).ok_or(GameError::ArithmeticOverflow)?
-                    .checked_div(100).ok_or(GameError::ArithmeticOverflow)?;
-                
-                // Calculate remainder to handle odd total_commission correctly
-                let remainder = total_commission.checked_rem(2).ok_or(GameError::ArithmeticOverflow)?;
-                let half_commission = total_commission.checked_div(2).ok_or(GameError::ArithmeticOverflow)?;
-
-                let (our_commission, referrer_commission) = if lobby.referrer.is_some() {
-                    // If referrer exists, split 5% equally: 2.5% each
-                    // Don't add remainder to our_commission to avoid rent-exempt issues
-                    (half_commission, half_commission)
-                } else {
-                    // If no referrer, we get total commission minus remainder to keep vault rent-exempt
-                    (total_commission.checked_sub(remainder).ok_or(GameError::ArithmeticOverflow)?, 0)
-                };
-                
-                // Calculate commission per player using ceiling division to include remainder
-                let commission_per_player = total_commission.checked_add(1).ok_or(GameError::ArithmeticOverflow)?
-                    .checked_div(2).ok_or(GameError::ArithmeticOverflow)?;
-                let refund_per_player = lobby.bet_amount.checked_sub(commission_per_player).ok_or(GameError::ArithmeticOverflow)?;
-                
-                let vault_balance = ctx.accounts.vault.lamports();
-                let rent_exempt_amount = Rent::get()?.minimum_balance(0);
-                
-                require!(vault_balance >= total_pool + rent_exempt_amount, GameError::InsufficientVaultBalance);
-                
-                // Store our commission amount in contract state for tracking
-                let contract_state = &mut ctx.accounts.contract_state;
-                contract_state.accumulated_commission = contract_state.accumulated_commission.checked_add(our_commission).ok_or(GameError::ArithmeticOverflow)?;
-                
-                // Transfer our commission to commission vault
-                ctx.accounts.vault.sub_lamports(our_commission)?;
-                ctx.accounts.commission_vault.add_lamports(our_commission)?;
-                
-                // Transfer referrer commission if referrer exists and account provided
-                if let Some(referrer_key) = lobby.referrer {
-                    if let Some(referrer_account) = &ctx.accounts.referrer {
-                        // Validate referrer account matches the one stored in lobby
-                        require!(referrer_account.key() == referrer_key, GameError::InvalidReferrer);
-                        
-                        // Check if referrer account has sufficient balance to remain rent-exempt after receiving commission
-                        let current_balance = referrer_account.lamports();
-                        let rent_exempt_minimum = Rent::get()?.minimum_balance(0);
-                        
-                        // Only transfer if referrer account can safely receive funds
-                        // If account doesn't exist or has insufficient rent, add commission to our vault instead
-                        if current_balance > 0 || referrer_commission >= rent_exempt_minimum {
-                            ctx.accounts.vault.sub_lamports(referrer_commission)?;
-                            referrer_account.add_lamports(referrer_commission)?;
-                        } else {
-                            // Add referrer's commission to our commission (safer fallback)
-                            contract_state.accumulated_commission = contract_state.accumulated_commission
-                                .checked_add(referrer_commission).ok_or(GameError::ArithmeticOverflow)?;
-                            ctx.accounts.vault.sub_lamports(referrer_commission)?;
-                            ctx.accounts.commission_vault.add_lamports(referrer_commission)?;
-                        }
-                    } else {
-                        // If referrer account not provided, add referrer's commission to our commission
-                        contract_state.accumulated_commission = contract_state.accumulated_commission
-                            .checked_add(referrer_commission).ok_or(GameError::ArithmeticOverflow)?;
-                        ctx.accounts.vault.sub_lamports(referrer_commission)?;
-                        ctx.accounts.commission_vault.add_lamports(referrer_commission)?;
-                    }
-                }
-                
-                // Validate creator account matches lobby creator
-                require!(ctx.accounts.creator.key() == lobby.creator, GameError::InvalidCreator);
-                
-                // Refund creator (minus commission)
-                ctx.accounts.vault.sub_lamports(refund_per_player)?;
-                ctx.accounts.creator.add_lamports(refund_per_player)?;
-                
-                // Validate and refund opponent (minus commission)
-                let opponent_key = lobby.opponent.ok_or(GameError::OpponentNotFound)?;
-                require!(ctx.accounts.opponent.key() == opponent_key, GameError::InvalidOpponent);
-                
-                ctx.accounts.vault.sub_lamports(refund_per_player)?;
-                ctx.accounts.opponent.add_lamports(refund_per_player)?;
-            },
-            LobbyStatus::Completed => {
-                return Err(GameError::GameAlreadyCompleted.into());
+        // Registration that never fills, or a round the backend never finishes, refunds every entrant in full
+        let timeout_threshold = match tournament.status {
+            TournamentStatus::Registration => tournament.created_at.checked_add(tournament.registration_timeout_seconds),
+            TournamentStatus::InProgress => {
+                let round_start = tournament.round_started_at.ok_or(GameError::GameNotStarted)?;
+                round_start.checked_add(tournament.match_timeout_seconds)
             },
-            LobbyStatus::Cancelled => {
-                return Err(GameError::GameAlreadyCancelled.into());
-            },
-            LobbyStatus::Draw => {
-                return Err(GameError::GameAlreadyCompleted.into());
-            }
+            _ => return Err(GameError::TournamentNotInProgress.into()),
         }
+        .ok_or(GameError::ArithmeticOverflow)?;
+        require!(clock.unix_timestamp >= timeout_threshold, GameError::TimeoutNotReached);
         
-        lobby.status = LobbyStatus::Cancelled;
+        // Refund destinations are passed in entrant order: wallets for SOL tournaments, their token accounts for SPL ones
+        require!(ctx.remaining_accounts.len() == tournament.entrants.len(), GameError::InvalidPayoutAccount);
         
-        emit!(GameTimeoutCancelled {
-            lobby_id: lobby.id.clone(),
-            canceller,
+        let tournament_key = tournament.key();
+        let vault_bump = [ctx.bumps.vault];
+        let vault_seeds: &[&[u8]] = &[b"tournament_vault", tournament_key.as_ref(), &vault_bump];
+        let tournament_vault = LobbyVault {
+            vault: &ctx.accounts.vault,
+            signer_seeds: vault_seeds,
+            token: vault_token(tournament.mint, &ctx.accounts.mint, &ctx.accounts.vault_token_account, &ctx.accounts.token_program)?,
+        };
+        require!(tournament_vault.stake_balance()? >= tournament.total_escrowed()?, GameError::InsufficientVaultBalance);
+        
+        // Update state before transfers; no commission is ever taken before the final
+        tournament.status = TournamentStatus::Cancelled;
+        tournament.completed_at = Some(clock.unix_timestamp);
+        
+        for (entrant, destination) in tournament.entrants.iter().zip(ctx.remaining_accounts.iter()) {
+            let refund_destination = tournament_vault.player_payout_account(entrant.player, destination)?;
+            tournament_vault.transfer_out(&refund_destination, entrant.escrowed)?;
+        }
+        
+        emit!(TournamentCancelled {
+            tournament_id: tournament.id.clone(),
+            canceller: ctx.accounts.canceller.key(),
             timestamp: clock.unix_timestamp,
         });
         
         Ok(())
     }
 
-    pub fn close_lobby(ctx: Context<CloseLobby>) -> Result<()> {
-        let lobby = &ctx.accounts.lobby;
+    pub fn close_tournament(ctx: Context<CloseTournament>) -> Result<()> {
+        let tournament = &ctx.accounts.tournament;
         
-        // Ensure game is completely finished
+        // Prizes or refunds must all have been paid out
         require!(
-            matches!(
-                lobby.status,
-                LobbyStatus::Completed | LobbyStatus::Cancelled | LobbyStatus::Draw
-            ),
+            matches!(tournament.status, TournamentStatus::Completed | TournamentStatus::Cancelled),
             GameError::GameNotFinished
         );
         
         // Ensure vault only contains rent-exempt minimum
         let rent_min = Rent::get()?.minimum_balance(0);
+        require!(ctx.accounts.vault.lamports() == rent_min, GameError::VaultNotEmpty);
+        
+        let tournament_key = tournament.key();
+        let vault_bump = [ctx.bumps.vault];
+        let vault_seeds: &[&[u8]] = &[b"tournament_vault", tournament_key.as_ref(), &vault_bump];
+        close_pool_vaults(
+            tournament.mint,
+            &ctx.accounts.vault,
+            vault_seeds,
+            &ctx.accounts.organizer,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        
+        // Tournament account will be closed automatically by the close attribute
+        
+        Ok(())
+    }
+
+    pub fn open_spectator_pool(ctx: Context<OpenSpectatorPool>) -> Result<()> {
+        let lobby = &ctx.accounts.lobby;
+        let global_config = &ctx.accounts.global_config;
+        let clock = Clock::get()?;
+        
+        // Pools settle on the single winner that claim_prize records
         require!(
-            ctx.accounts.vault.lamports() == rent_min,
-            GameError::VaultNotEmpty
+            lobby.payout_bps.is_empty() && !lobby.team_mode && lobby.series_length <= 1,
+            GameError::SpectatorPoolUnsupported
         );
+        require!(lobby.status == LobbyStatus::InProgress, GameError::GameNotInProgress);
+        let game_start = lobby.game_started_at.ok_or(GameError::GameNotStarted)?;
         
-        // Close vault manually since we can't use close attribute on AccountInfo
-        let vault_balance = ctx.accounts.vault.lamports();
-        ctx.accounts.vault.sub_lamports(vault_balance)?;
-        ctx.accounts.creator.add_lamports(vault_balance)?;
+        // Spectator pools are always in SOL, whatever the lobby stakes
+        let pool = &mut ctx.accounts.spectator_pool;
+        pool.lobby = lobby.key();
+        pool.lobby_id = lobby.id.clone();
+        pool.backed_totals = vec![0; lobby.players.len()];
+        pool.commission_bps = global_config.spectator_commission_bps;
+        pool.cutoff_at = game_start.checked_add(global_config.spectator_cutoff_seconds)
+            .ok_or(GameError::ArithmeticOverflow)?;
+        pool.status = SpectatorPoolStatus::Open;
+        pool.winner_index = None;
+        pool.payout_pool = 0;
+        pool.opener = ctx.accounts.payer.key();
+        pool.open_bets = 0;
+        pool.settled_at = None;
+        pool.bump = ctx.bumps.spectator_pool;
         
-        // Lobby account will be closed automatically by the close attribute
+        emit!(SpectatorPoolOpened {
+            lobby_id: lobby.id.clone(),
+            commission_bps: pool.commission_bps,
+            cutoff_at: pool.cutoff_at,
+            timestamp: clock.unix_timestamp,
+        });
         
         Ok(())
     }
-}
+
+    pub fn place_spectator_bet(ctx: Context<PlaceSpectatorBet>, backed_player: Pubkey, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_JOIN_LOBBY), GameError::InstructionPaused);
+        
+        let lobby = &ctx.accounts.lobby;
+        let clock = Clock::get()?;
+        let spectator = ctx.accounts.spectator.key();
+        
+        require!(ctx.accounts.spectator_pool.status == SpectatorPoolStatus::Open, GameError::SpectatorPoolClosed);
+        require!(lobby.status == LobbyStatus::InProgress, GameError::GameNotInProgress);
+        require!(clock.unix_timestamp < ctx.accounts.spectator_pool.cutoff_at, GameError::BettingClosed);
+        
+        // Players can't bet on their own game
+        require!(!lobby.is_player(&spectator), GameError::PlayersCannotSpectate);
+        let player_index = lobby.player_index(&backed_player).ok_or(GameError::InvalidWinner)?;
+        require!(amount >= MIN_BET_AMOUNT, GameError::BetAmountTooSmall);
+        
+        transfer_to_vault(
+            &ctx.accounts.spectator,
+            &ctx.accounts.spectator_vault,
+            None,
+            &None,
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        
+        let pool = &mut ctx.accounts.spectator_pool;
+        pool.backed_totals[player_index] = pool.backed_totals[player_index].checked_add(amount)
+            .ok_or(GameError::ArithmeticOverflow)?;
+        pool.open_bets = pool.open_bets.checked_add(1).ok_or(GameError::ArithmeticOverflow)?;
+        
+        // One bet per spectator and lobby, the bet account is created by Anchor with init attribute
+        let bet = &mut ctx.accounts.spectator_bet;
+        bet.pool = pool.key();
+        bet.spectator = spectator;
+        bet.player_index = player_index as u8;
+        bet.amount = amount;
+        bet.bump = ctx.bumps.spectator_bet;
+        
+        emit!(SpectatorBetPlaced {
+            lobby_id: lobby.id.clone(),
+            spectator,
+            backed_player,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn settle_spectator_pool(ctx: Context<SettleSpectatorPool>) -> Result<()> {
+        let lobby = &ctx.accounts.lobby;
+        let pool = &mut ctx.accounts.spectator_pool;
+        let clock = Clock::get()?;
+        
+        require!(pool.status == SpectatorPoolStatus::Open, GameError::SpectatorPoolClosed);
+        
+        let winner_index = pool.outcome(lobby)?;
+        
+        let total_pool = pool.backed_totals.iter().try_fold(0u64, |total, backed| total.checked_add(*backed))
+            .ok_or(GameError::ArithmeticOverflow)?;
+        let commission = match winner_index {
+            Some(index) => {
+                // Commission is only taken from settled pools, at the rate snapshotted when the pool opened
+                let commission = calculate_commission(total_pool, pool.commission_bps)?;
+                pool.status = SpectatorPoolStatus::Settled;
+                pool.winner_index = Some(index as u8);
+                pool.payout_pool = total_pool.checked_sub(commission).ok_or(GameError::ArithmeticOverflow)?;
+                commission
+            },
+            None => {
+                pool.status = SpectatorPoolStatus::Void;
+                0
+            },
+        };
+        pool.settled_at = Some(clock.unix_timestamp);
+        
+        if commission > 0 {
+            let lobby_key = lobby.key();
+            let vault_bump = [ctx.bumps.spectator_vault];
+            let vault_seeds: &[&[u8]] = &[b"spectator_vault", lobby_key.as_ref(), &vault_bump];
+            let spectator_vault = LobbyVault {
+                vault: &ctx.accounts.spectator_vault,
+                signer_seeds: vault_seeds,
+                token: None,
+            };
+            spectator_vault.transfer_out(&ctx.accounts.commission_vault, commission)?;
+            ctx.accounts.contract_state.credit_commission(None, commission)?;
+        }
+        
+        emit!(SpectatorPoolSettled {
+            lobby_id: lobby.id.clone(),
+            winner: winner_index.map(|index| lobby.players[index].player),
+            total_pool,
+            commission,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn claim_spectator_payout(ctx: Context<ClaimSpectatorPayout>) -> Result<()> {
+        let pool = &mut ctx.accounts.spectator_pool;
+        let bet = &ctx.accounts.spectator_bet;
+        let clock = Clock::get()?;
+        
+        let payout = pool.payout(bet)?;
+        
+        // Works from the pool alone, the lobby itself may already be closed
+        let vault_bump = [ctx.bumps.spectator_vault];
+        let vault_seeds: &[&[u8]] = &[b"spectator_vault", pool.lobby.as_ref(), &vault_bump];
+        let spectator_vault = LobbyVault {
+            vault: &ctx.accounts.spectator_vault,
+            signer_seeds: vault_seeds,
+            token: None,
+        };
+        spectator_vault.transfer_out(&ctx.accounts.spectator.to_account_info(), payout)?;
+        pool.open_bets = pool.open_bets.checked_sub(1).ok_or(GameError::ArithmeticOverflow)?;
+        
+        // Bet account is closed to the spectator by the close attribute, so it can't be claimed twice
+        
+        emit!(SpectatorPayoutClaimed {
+            lobby_id: pool.lobby_id.clone(),
+            spectator: ctx.accounts.spectator.key(),
+            amount: payout,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn close_spectator_pool(ctx: Context<CloseSpectatorPool>) -> Result<()> {
+        let pool = &ctx.accounts.spectator_pool;
+        let clock = Clock::get()?;
+        
+        pool.check_closable(clock.unix_timestamp)?;
+        
+        // Rounding dust and winnings left unclaimed go to the commission vault. Void pools only close once
+        // every refund is claimed, so nothing owed to a backer is ever swept.
+        let spectator_vault = &ctx.accounts.spectator_vault;
+        let rent_exempt_amount = Rent::get()?.minimum_balance(0);
+        let unclaimed = spectator_vault.lamports().saturating_sub(rent_exempt_amount);
+        if unclaimed > 0 {
+            spectator_vault.sub_lamports(unclaimed)?;
+            ctx.accounts.commission_vault.add_lamports(unclaimed)?;
+            ctx.accounts.contract_state.credit_commission(None, unclaimed)?;
+        }
+        
+        // Close vault manually since we can't use close attribute on AccountInfo, its rent goes back to the opener
+        let vault_balance = spectator_vault.lamports();
+        spectator_vault.sub_lamports(vault_balance)?;
+        ctx.accounts.opener.add_lamports(vault_balance)?;
+        
+        // Pool account is closed to the opener by the close attribute
+        
+        emit!(SpectatorPoolSwept {
+            lobby_id: pool.lobby_id.clone(),
+            unclaimed_bets: pool.open_bets,
+            swept_amount: unclaimed,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn sponsor_lobby(ctx: Context<SponsorLobby>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_JOIN_LOBBY), GameError::InstructionPaused);
+        
+        let lobby = &mut ctx.accounts.lobby;
+        let clock = Clock::get()?;
+        
+        // Bonus goes to a single winner, so only lobbies settled by claim_prize or record_round can be sponsored
+        require!(lobby.payout_bps.is_empty() && !lobby.team_mode, GameError::BonusUnsupported);
+        // Promotional matches are usually funded before they start. A sponsored lobby can't be cancelled by its
+        // creator, but cancel_game_timeout still refunds it and leaves it open for settle_bonus.
+        require!(
+            matches!(lobby.status, LobbyStatus::Waiting | LobbyStatus::InProgress),
+            GameError::LobbyNotAvailable
+        );
+        require!(amount >= MIN_BET_AMOUNT, GameError::BetAmountTooSmall);
+        
+        // Bonus is escrowed on the sponsor's own contribution account, one per sponsor and lobby
+        let transfer_instruction = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.sponsor.to_account_info(),
+            to: ctx.accounts.bonus_contribution.to_account_info(),
+        };
+        anchor_lang::system_program::transfer(
+            CpiContext::new(ctx.accounts.system_program.to_account_info(), transfer_instruction),
+            amount,
+        )?;
+        
+        let contribution = &mut ctx.accounts.bonus_contribution;
+        contribution.lobby = lobby.key();
+        contribution.sponsor = ctx.accounts.sponsor.key();
+        contribution.amount = amount;
+        // Snapshot the bonus commission so later config changes never affect this sponsorship
+        contribution.commission_bps = ctx.accounts.global_config.bonus_commission_bps;
+        contribution.bump = ctx.bumps.bonus_contribution;
+        
+        lobby.add_bonus(amount)?;
+        
+        emit!(BonusSponsored {
+            lobby_id: lobby.id.clone(),
+            sponsor: contribution.sponsor,
+            amount,
+            total_bonus: lobby.bonus_amount,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn settle_bonus(ctx: Context<SettleBonus>) -> Result<()> {
+        let lobby = &mut ctx.accounts.lobby;
+        let contribution = &ctx.accounts.bonus_contribution;
+        let clock = Clock::get()?;
+        
+        let (recipient, commission, paid) = match lobby.bonus_winner()? {
+            Some(winner_key) => {
+                let winner = ctx.accounts.winner.as_ref().ok_or(GameError::InvalidWinner)?;
+                require!(winner.key() == winner_key, GameError::InvalidWinner);
+                
+                // No commission on bonuses unless one was configured when the sponsorship was made
+                let commission = calculate_commission(contribution.amount, contribution.commission_bps)?;
+                let paid = contribution.amount.checked_sub(commission).ok_or(GameError::ArithmeticOverflow)?;
+                
+                let contribution_vault = LobbyVault {
+                    vault: &contribution.to_account_info(),
+                    signer_seeds: &[],
+                    token: None,
+                };
+                contribution_vault.transfer_out(winner, paid)?;
+                contribution_vault.transfer_out(&ctx.accounts.commission_vault, commission)?;
+                ctx.accounts.contract_state.credit_commission(None, commission)?;
+                
+                (winner_key, commission, paid)
+            },
+            // Closing the contribution account below returns the bonus to the sponsor along with its rent
+            None => (contribution.sponsor, 0, contribution.amount),
+        };
+        
+        lobby.open_bonus_contributions = lobby.open_bonus_contributions.checked_sub(1)
+            .ok_or(GameError::ArithmeticOverflow)?;
+        
+        // Contribution account is closed to the sponsor by the close attribute
+        
+        emit!(BonusSettled {
+            lobby_id: lobby.id.clone(),
+            sponsor: contribution.sponsor,
+            recipient,
+            amount: paid,
+            commission,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn claim_commission(ctx: Context<ClaimCommission>) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_CLAIM_COMMISSION), GameError::InstructionPaused);
+        
+        // Withdrawals must be approved by the multisig threshold first
+        let proposal = &mut ctx.accounts.proposal;
+        let amount = match proposal.action {
+            ProposalAction::WithdrawCommission { amount } => amount,
+            _ => return Err(GameError::InvalidProposalAction.into()),
+        };
+        execute_approved_proposal(proposal, &ctx.accounts.multisig)?;
+        
+        let contract_state = &mut ctx.accounts.contract_state;
+        
+        require!(contract_state.accumulated_commission >= amount, GameError::InsufficientCommission);
+        
+        // Validate commission vault has sufficient balance
+        let vault_balance = ctx.accounts.commission_vault.lamports();
+        require!(vault_balance >= amount, GameError::InsufficientVaultBalance);
+        
+        contract_state.accumulated_commission = contract_state.accumulated_commission.checked_sub(amount).ok_or(GameError::ArithmeticOverflow)?;
+        
+        // Transfer from commission vault to claimer using safe methods
+        // Keep rent-exempt amount in commission vault
+        let rent_exempt_amount = Rent::get()?.minimum_balance(0);
+        let remaining_balance = vault_balance.checked_sub(amount).ok_or(GameError::ArithmeticOverflow)?;
+        require!(remaining_balance >= rent_exempt_amount, GameError::InsufficientVaultBalance);
+        
+        ctx.accounts.commission_vault.sub_lamports(amount)?;
+        ctx.accounts.commission_claimer.add_lamports(amount)?;
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        
+        emit!(CommissionClaimed {
+            claimer: ctx.accounts.commission_claimer.key(),
+            mint: None,
+            amount,
+            timestamp,
+        });
+        
+        emit!(ProposalExecuted {
+            proposal_id: ctx.accounts.proposal.id,
+            executor: ctx.accounts.executor.key(),
+            timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn claim_token_commission(ctx: Context<ClaimTokenCommission>) -> Result<()> {
+        require!(!ctx.accounts.global_config.is_paused(PAUSE_CLAIM_COMMISSION), GameError::InstructionPaused);
+        
+        // Withdrawals must be approved by the multisig threshold first
+        let proposal = &mut ctx.accounts.proposal;
+        let amount = match proposal.action {
+            ProposalAction::WithdrawTokenCommission { mint, amount } if mint == ctx.accounts.mint.key() => amount,
+            _ => return Err(GameError::InvalidProposalAction.into()),
+        };
+        execute_approved_proposal(proposal, &ctx.accounts.multisig)?;
+        
+        let mint = &ctx.accounts.mint;
+        ctx.accounts.contract_state.debit_commission(Some(mint.key()), amount)?;
+        
+        // Validate commission token vault has sufficient balance
+        require!(ctx.accounts.commission_token_vault.amount >= amount, GameError::InsufficientVaultBalance);
+        
+        // Commission vault PDA owns every commission token vault
+        let commission_vault_bump = [ctx.bumps.commission_vault];
+        let commission_vault_seeds: &[&[u8]] = &[b"commission_vault", &commission_vault_bump];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.commission_token_vault.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: ctx.accounts.commission_claimer_token_account.to_account_info(),
+                    authority: ctx.accounts.commission_vault.to_account_info(),
+                },
+                &[commission_vault_seeds],
+            ),
+            amount,
+            mint.decimals,
+        )?;
+        
+        let timestamp = Clock::get()?.unix_timestamp;
+        
+        emit!(CommissionClaimed {
+            claimer: ctx.accounts.global_config.commission_claimer,
+            mint: Some(mint.key()),
+            amount,
+            timestamp,
+        });
+        
+        emit!(ProposalExecuted {
+            proposal_id: ctx.accounts.proposal.id,
+            executor: ctx.accounts.executor.key(),
+            timestamp,
+        });
+        
+        Ok(())
+    }
+
+
+    pub fn distribute_commission<'info>(ctx: Context<'_, '_, 'info, 'info, DistributeCommission<'info>>) -> Result<()> {
+        let global_config = &ctx.accounts.global_config;
+        require!(!global_config.is_paused(PAUSE_CLAIM_COMMISSION), GameError::InstructionPaused);
+        
+        // Destinations are passed in the configured order: wallets for SOL, their token accounts for a mint
+        let recipients = &global_config.treasury_recipients;
+        require!(!recipients.is_empty(), GameError::TreasuryRecipientsNotSet);
+        require!(ctx.remaining_accounts.len() == recipients.len(), GameError::InvalidTreasuryRecipients);
+        
+        let mint = ctx.accounts.mint.as_ref().map(|mint| mint.key());
+        let commission_vault_bump = [ctx.bumps.commission_vault];
+        let commission_vault_seeds: &[&[u8]] = &[b"commission_vault", &commission_vault_bump];
+        // The commission vault pays out like a lobby vault, signing token transfers as the commission token vault authority
+        let commission_payer = LobbyVault {
+            vault: &ctx.accounts.commission_vault,
+            signer_seeds: commission_vault_seeds,
+            token: vault_token(mint, &ctx.accounts.mint, &ctx.accounts.commission_token_vault, &ctx.accounts.token_program)?,
+        };
+        
+        // Only the recorded ledger is distributed, the rent-exempt reserve stays in the vault
+        let contract_state = &mut ctx.accounts.contract_state;
+        let distributable = *contract_state.commission_ledger(mint)?;
+        let shares = treasury_shares(distributable, recipients)?;
+        let timestamp = Clock::get()?.unix_timestamp;
+        
+        let mut distributed: u64 = 0;
+        for ((recipient, destination), amount) in recipients.iter().zip(ctx.remaining_accounts.iter()).zip(shares) {
+            match mint {
+                None => {
+                    require!(destination.key() == recipient.recipient, GameError::InvalidTreasuryRecipients);
+                },
+                Some(mint) => {
+                    let token_account = InterfaceAccount::<TokenAccount>::try_from(destination)?;
+                    require!(token_account.owner == recipient.recipient, GameError::InvalidTreasuryRecipients);
+                    require!(token_account.mint == mint, GameError::InvalidMint);
+                },
+            }
+            
+            commission_payer.transfer_out(destination, amount)?;
+            distributed = distributed.checked_add(amount).ok_or(GameError::ArithmeticOverflow)?;
+            
+            emit!(CommissionDistributed {
+                recipient: recipient.recipient,
+                mint,
+                weight_bps: recipient.weight_bps,
+                amount,
+                timestamp,
+            });
+        }
+        
+        contract_state.debit_commission(mint, distributed)?;
+        
+        Ok(())
+    }
+
+    pub fn claim_draw_refund(ctx: Context<ClaimDrawRefund>, game_signatures: Vec<[u8; 64]>, nonce: u64) -> Result<()> {
+        let lobby = &mut ctx.accounts.lobby;
+        let clock = Clock::get()?;
+        let claimer = ctx.accounts.claimer.key();
+        
+        // Ensure lobby is in progress or already marked as draw
+        require!(
+            lobby.status == LobbyStatus::InProgress || lobby.status == LobbyStatus::Draw, 
+            GameError::GameNotInProgress
+        );
+        
+        // Validate claimer is a legitimate participant
+        let claimer_index = lobby.player_index(&claimer).ok_or(GameError::InvalidClaimer)?;
+        
+        // Ensure other players exist (can't have draw alone)
+        require!(lobby.players.len() >= MIN_PLAYERS as usize, GameError::OpponentNotFound);
+        
+        // Check if claimer has already claimed their refund
+        require!(!lobby.players[claimer_index].claimed, GameError::RefundAlreadyClaimed);
+        
+        // Verify the claimer account is actually signing this transaction
+        require!(ctx.accounts.claimer.is_signer, GameError::ClaimerMustSign);
+        
+        // Prevent replay attacks by including nonce in signature
+        let message = format!("draw:{}:{}:{}", lobby.id, claimer, nonce);
+        let message_bytes = message.as_bytes();
+        
+        // Verify at least oracle_threshold registered oracles signed the message using instruction sysvar
+        verify_oracle_signatures(
+            &ctx.accounts.instruction_sysvar,
+            &ctx.accounts.global_config,
+            message_bytes,
+            &game_signatures,
+        )?;
+        
+        // Commission at the rate snapshotted when the lobby was created, shared equally by every refund
+        let (payable_commission, refund_amount) = lobby.draw_refund(claimer_index)?;
+        
+        let lobby_key = lobby.key();
+        let vault_bump = [ctx.bumps.vault];
+        let vault_seeds: &[&[u8]] = &[b"vault", lobby_key.as_ref(), &vault_bump];
+        let lobby_vault = LobbyVault {
+            vault: &ctx.accounts.vault,
+            signer_seeds: vault_seeds,
+            token: vault_token(lobby.mint, &ctx.accounts.mint, &ctx.accounts.vault_token_account, &ctx.accounts.token_program)?,
+        };
+        
+        // Handle commission transfers only if commission hasn't been taken yet
+        if !lobby.commission_taken_draw {
+            // Split the payable commission with the referrer
+            let (our_commission, referrer_commission) = split_commission(
+                payable_commission,
+                lobby.referrer_share_bps,
+                lobby.referrer.is_some(),
+            )?;
+            
+            // Transfer our commission to commission vault, and referrer commission if the referrer can receive it
+            let commission_destination = lobby_vault.commission_account(&ctx.accounts.commission_vault, &ctx.accounts.commission_token_vault)?;
+            let credited_commission = lobby_vault.pay_commission(
+                &commission_destination,
+                lobby.referrer,
+                &ctx.accounts.referrer,
+                &ctx.accounts.referrer_token_account,
+                our_commission,
+                referrer_commission,
+            )?;
+            
+            // Store our commission amount in contract state for tracking, per asset
+            ctx.accounts.contract_state.credit_commission(lobby.mint, credited_commission)?;
+            
+            // Mark commission as taken
+            lobby.commission_taken_draw = true;
+        }
+        
+        // Validate vault has sufficient balance before transfers (excluding rent-exempt amount)
+        require!(lobby_vault.stake_balance()? >= refund_amount, GameError::InsufficientVaultBalance);
+        let rent_exempt_amount = Rent::get()?.minimum_balance(0);
+        
+        // Transfer refund to claimer
+        let claimer_destination = lobby_vault.payout_account(&ctx.accounts.claimer.to_account_info(), &ctx.accounts.claimer_token_account)?;
+        lobby_vault.transfer_out(&claimer_destination, refund_amount)?;
+        
+        // Mark this participant as having claimed their refund
+        lobby.players[claimer_index].claimed = true;
+        
+        // Set lobby status to Draw and completion time if this is the first claim
+        if lobby.status == LobbyStatus::InProgress {
+            lobby.status = LobbyStatus::Draw;
+            lobby.completed_at = Some(clock.unix_timestamp);
+            
+            emit!(GameDeclaredDraw {
+                lobby_id: lobby.id.clone(),
+                timestamp: clock.unix_timestamp,
+            });
+        }
+        
+        // Ensure vault retains rent-exempt status
+        let remaining_balance = ctx.accounts.vault.lamports();
+        require!(remaining_balance >= rent_exempt_amount, GameError::InsufficientVaultBalance);
+        
+        emit!(DrawRefundClaimed {
+            lobby_id: lobby.id.clone(),
+            claimer,
+            refund_amount,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+
+    pub fn cancel_game_timeout<'info>(ctx: Context<'_, '_, '_, 'info, CancelGameTimeout<'info>>) -> Result<()> {
+        let lobby = &mut ctx.accounts.lobby;
+        let clock = Clock::get()?;
+        let canceller = ctx.accounts.canceller.key();
+        
+        // Validate canceller is a participant in the game
+        require!(lobby.is_player(&canceller), GameError::OnlyParticipantsCanCancel);
+        
+        // Refund destinations are passed in player order: wallets for SOL lobbies, their token accounts for SPL lobbies
+        require!(ctx.remaining_accounts.len() == lobby.players.len(), GameError::InvalidPayoutAccount);
+        
+        let lobby_key = lobby.key();
+        let vault_bump = [ctx.bumps.vault];
+        let vault_seeds: &[&[u8]] = &[b"vault", lobby_key.as_ref(), &vault_bump];
+        let lobby_vault = LobbyVault {
+            vault: &ctx.accounts.vault,
+            signer_seeds: vault_seeds,
+            token: vault_token(lobby.mint, &ctx.accounts.mint, &ctx.accounts.vault_token_account, &ctx.accounts.token_program)?,
+        };
+        
+        let total_pool = lobby.total_escrowed()?;
+        require!(lobby_vault.stake_balance()? >= total_pool, GameError::InsufficientVaultBalance);
+        
+        // Check timeout conditions based on lobby status
+        let commission_per_player = match lobby.status {
+            LobbyStatus::Waiting => {
+                // Join timeout from lobby creation
+                let timeout_threshold = lobby.created_at.checked_add(lobby.join_timeout_seconds)
+                    .ok_or(GameError::ArithmeticOverflow)?;
+                require!(
+                    clock.unix_timestamp >= timeout_threshold,
+                    GameError::TimeoutNotReached
+                );
+                
+                // No game was played, so everyone who joined gets their full stake back
+                0
+            },
+            LobbyStatus::InProgress => {
+                // Play timeout from the start of the current round, which is the game start outside series
+                let round_start = lobby.round_started_at.ok_or(GameError::GameNotStarted)?;
+                let timeout_threshold = round_start.checked_add(lobby.play_timeout_seconds)
+                    .ok_or(GameError::ArithmeticOverflow)?;
+                require!(
+                    clock.unix_timestamp >= timeout_threshold,
+                    GameError::TimeoutNotReached
+                );
+                
+                // Calculate total commission at the rate snapshotted when the lobby was created
+                let total_commission = calculate_commission(total_pool, lobby.commission_bps)?;
+                let (payable_commission, commission_per_player) = refund_commission(total_commission, lobby.players.len())?;
+                
+                // Split the payable commission with the referrer
+                let (our_commission, referrer_commission) = split_commission(
+                    payable_commission,
+                    lobby.referrer_share_bps,
+                    lobby.referrer.is_some(),
+                )?;
+                
+                // Transfer our commission to commission vault, and referrer commission if the referrer can receive it
+                let commission_destination = lobby_vault.commission_account(&ctx.accounts.commission_vault, &ctx.accounts.commission_token_vault)?;
+                let credited_commission = lobby_vault.pay_commission(
+                    &commission_destination,
+                    lobby.referrer,
+                    &ctx.accounts.referrer,
+                    &ctx.accounts.referrer_token_account,
+                    our_commission,
+                    referrer_commission,
+                )?;
+                
+                // Store our commission amount in contract state for tracking, per asset
+                ctx.accounts.contract_state.credit_commission(lobby.mint, credited_commission)?;
+                
+                commission_per_player
+            },
+            LobbyStatus::Completed => {
+                return Err(GameError::GameAlreadyCompleted.into());
+            },
+            LobbyStatus::Cancelled => {
+                return Err(GameError::GameAlreadyCancelled.into());
+            },
+            LobbyStatus::Draw => {
+                return Err(GameError::GameAlreadyCompleted.into());
+            }
+        };
+        
+        // Refund every player (minus commission)
+        for (player, destination) in lobby.players.iter_mut().zip(ctx.remaining_accounts.iter()) {
+            let refund_amount = player.escrowed.checked_sub(commission_per_player).ok_or(GameError::ArithmeticOverflow)?;
+            let player_destination = lobby_vault.player_payout_account(player.player, destination)?;
+            lobby_vault.transfer_out(&player_destination, refund_amount)?;
+            player.claimed = true;
+        }
+        
+        lobby.status = LobbyStatus::Cancelled;
+        lobby.completed_at = Some(clock.unix_timestamp);
+        
+        emit!(GameTimeoutCancelled {
+            lobby_id: lobby.id.clone(),
+            canceller,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn cancel_lobby(ctx: Context<CancelLobby>) -> Result<()> {
+        let lobby = &mut ctx.accounts.lobby;
+        let clock = Clock::get()?;
+        
+        lobby.check_creator_cancel()?;
+        
+        let lobby_key = lobby.key();
+        let vault_bump = [ctx.bumps.vault];
+        let vault_seeds: &[&[u8]] = &[b"vault", lobby_key.as_ref(), &vault_bump];
+        let lobby_vault = LobbyVault {
+            vault: &ctx.accounts.vault,
+            signer_seeds: vault_seeds,
+            token: vault_token(lobby.mint, &ctx.accounts.mint, &ctx.accounts.vault_token_account, &ctx.accounts.token_program)?,
+        };
+        
+        // Everything staked so far is the creator's, so the whole vault goes back to them
+        let refund_amount = lobby_vault.stake_balance()?;
+        require!(refund_amount >= lobby.total_escrowed()?, GameError::InsufficientVaultBalance);
+        
+        lobby.status = LobbyStatus::Cancelled;
+        lobby.completed_at = Some(clock.unix_timestamp);
+        lobby.players[0].claimed = true;
+        
+        let creator_destination = lobby_vault.payout_account(
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.creator_token_account,
+        )?;
+        lobby_vault.transfer_out(&creator_destination, refund_amount)?;
+        
+        // Token vaults are reloaded after the refund so closing sees them empty
+        if let Some(vault_token_account) = ctx.accounts.vault_token_account.as_mut() {
+            vault_token_account.reload()?;
+        }
+        close_pool_vaults(
+            lobby.mint,
+            &ctx.accounts.vault,
+            vault_seeds,
+            &ctx.accounts.creator,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        
+        // Lobby account will be closed automatically by the close attribute
+        
+        emit!(LobbyCancelled {
+            lobby_id: lobby.id.clone(),
+            creator: lobby.creator,
+            refund_amount,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn close_lobby(ctx: Context<CloseLobby>) -> Result<()> {
+        let lobby = &ctx.accounts.lobby;
+        
+        // Ensure game is completely finished
+        require!(
+            matches!(
+                lobby.status,
+                LobbyStatus::Completed | LobbyStatus::Cancelled | LobbyStatus::Draw
+            ),
+            GameError::GameNotFinished
+        );
+        
+        // Sponsors' bonuses must be paid out or refunded first
+        require!(lobby.open_bonus_contributions == 0, GameError::BonusNotSettled);
+        
+        // An open spectator pool still settles from this lobby's result
+        if !ctx.accounts.spectator_pool.data_is_empty() {
+            require!(*ctx.accounts.spectator_pool.owner == crate::ID, GameError::SpectatorPoolNotSettled);
+            let data = ctx.accounts.spectator_pool.try_borrow_data()?;
+            let spectator_pool = SpectatorPool::try_deserialize(&mut &data[..])?;
+            require!(spectator_pool.status != SpectatorPoolStatus::Open, GameError::SpectatorPoolNotSettled);
+        }
+        
+        // Ensure vault only contains rent-exempt minimum
+        let rent_min = Rent::get()?.minimum_balance(0);
+        require!(
+            ctx.accounts.vault.lamports() == rent_min,
+            GameError::VaultNotEmpty
+        );
+        
+        let lobby_key = lobby.key();
+        let vault_bump = [ctx.bumps.vault];
+        let vault_seeds: &[&[u8]] = &[b"vault", lobby_key.as_ref(), &vault_bump];
+        close_pool_vaults(
+            lobby.mint,
+            &ctx.accounts.vault,
+            vault_seeds,
+            &ctx.accounts.creator,
+            &ctx.accounts.mint,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        
+        // Lobby account will be closed automatically by the close attribute
+        
+        Ok(())
+    }
+
+    pub fn migrate_lobby(ctx: Context<MigrateLobby>) -> Result<()> {
+        let lobby_info = ctx.accounts.lobby.to_account_info();
+        
+        // Vault PDA is derived from the lobby address, so funds stay in place
+        let lobby: Lobby = upgrade_v1_account::<LobbyV1, Lobby>(
+            &lobby_info,
+            Lobby::DISCRIMINATOR,
+            LobbyV1::SPACE,
+            8 + Lobby::INIT_SPACE,
+        )?;
+        top_up_rent(&lobby_info, &ctx.accounts.payer, &ctx.accounts.system_program)?;
+        
+        emit!(LobbyMigrated {
+            lobby_id: lobby.id,
+            from_version: 1,
+            to_version: LOBBY_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    pub fn migrate_state(
+        ctx: Context<MigrateState>,
+        oracles: Vec<Pubkey>,
+        oracle_threshold: u8,
+        commission_claimer: Pubkey,
+    ) -> Result<()> {
+        let state_info = ctx.accounts.contract_state.to_account_info();
+        upgrade_v1_account::<ContractStateV1, ContractState>(
+            &state_info,
+            ContractState::DISCRIMINATOR,
+            ContractStateV1::SPACE,
+            8 + ContractState::INIT_SPACE,
+        )?;
+        top_up_rent(&state_info, &ctx.accounts.authority, &ctx.accounts.system_program)?;
+        
+        // Deployments from before GlobalConfig existed get it created here instead of in initialize
+        init_global_config(
+            &mut ctx.accounts.global_config,
+            ctx.accounts.authority.key(),
+            oracles,
+            oracle_threshold,
+            commission_claimer,
+            ctx.bumps.global_config,
+        )?;
+        
+        emit!(StateMigrated {
+            from_version: 1,
+            to_version: CONTRACT_STATE_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+// Sets up a fresh GlobalConfig with default rates and limits
+fn init_global_config(
+    global_config: &mut GlobalConfig,
+    admin: Pubkey,
+    oracles: Vec<Pubkey>,
+    oracle_threshold: u8,
+    commission_claimer: Pubkey,
+    bump: u8,
+) -> Result<()> {
+    validate_signer_set(&oracles, oracle_threshold, MAX_ORACLES, GameError::InvalidOracleSet)?;
+    require!(commission_claimer != Pubkey::default(), GameError::InvalidAuthority);
+    
+    global_config.admin = admin;
+    global_config.pending_admin = None;
+    global_config.oracles = oracles;
+    global_config.oracle_threshold = oracle_threshold;
+    global_config.commission_claimer = commission_claimer;
+    global_config.treasury_recipients = Vec::new();
+    global_config.commission_bps = DEFAULT_COMMISSION_BPS;
+    global_config.referrer_share_bps = DEFAULT_REFERRER_SHARE_BPS;
+    global_config.paused_flags = 0;
+    global_config.max_bet_amount = DEFAULT_MAX_BET_AMOUNT;
+    global_config.bet_tiers = Vec::new();
+    global_config.min_timeout_seconds = DEFAULT_MIN_TIMEOUT_SECONDS;
+    global_config.max_timeout_seconds = DEFAULT_MAX_TIMEOUT_SECONDS;
+    global_config.timelock_delay = DEFAULT_TIMELOCK_DELAY_SECONDS;
+    global_config.spectator_commission_bps = DEFAULT_SPECTATOR_COMMISSION_BPS;
+    global_config.spectator_cutoff_seconds = DEFAULT_SPECTATOR_CUTOFF_SECONDS;
+    global_config.bonus_commission_bps = DEFAULT_BONUS_COMMISSION_BPS;
+    global_config.config_change_count = 0;
+    global_config.bump = bump;
+    
+    Ok(())
+}
+
+// Reads a pre-versioning account, rejecting anything that isn't exactly the v1 size
+pub fn read_v1_account<T: AnchorDeserialize>(account: &AccountInfo, discriminator: &[u8], v1_space: usize) -> Result<T> {
+    let data = account.try_borrow_data()?;
+    require!(data.len() >= 8 && &data[..8] == discriminator, GameError::InvalidAccountVersion);
+    require!(data.len() == v1_space, GameError::AccountAlreadyMigrated);
+    T::deserialize(&mut &data[8..]).map_err(|_| GameError::InvalidAccountVersion.into())
+}
+
+// Converts a v1 account in place: resizes it to `new_space` and rewrites it with the current layout.
+// Rent for the extra space is topped up separately by the instruction.
+pub fn upgrade_v1_account<V1: AnchorDeserialize, T: AccountSerialize + From<V1>>(
+    account: &AccountInfo,
+    discriminator: &[u8],
+    v1_space: usize,
+    new_space: usize,
+) -> Result<T> {
+    let upgraded = T::from(read_v1_account::<V1>(account, discriminator, v1_space)?);
+    
+    account.resize(new_space)?;
+    let mut data = account.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data[..];
+    upgraded.try_serialize(&mut writer)?;
+    
+    Ok(upgraded)
+}
+
+// Tops an account up to rent exemption at its current size, paid by `payer`
+fn top_up_rent<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(account.data_len());
+    let current_lamports = account.lamports();
+    if current_lamports < required_lamports {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: payer.to_account_info(),
+                to: account.clone(),
+            },
+        );
+        anchor_lang::system_program::transfer(
+            cpi_context,
+            required_lamports.checked_sub(current_lamports).ok_or(GameError::ArithmeticOverflow)?,
+        )?;
+    }
+    
+    Ok(())
+}
+
+// Starts the notice period for a config-changing proposal once it first reaches the threshold
+pub fn start_proposal_timelock(proposal: &mut Proposal, multisig: &Multisig, timelock_delay: i64, now: i64) -> Result<()> {
+    if proposal.eta.is_some() || !proposal.action.is_timelocked() {
+        return Ok(());
+    }
+    if multisig.approval_count(&proposal.approvals) < multisig.threshold as usize {
+        return Ok(());
+    }
+    
+    let eta = now.checked_add(timelock_delay).ok_or(GameError::ArithmeticOverflow)?;
+    proposal.eta = Some(eta);
+    
+    emit!(ProposalQueued {
+        proposal_id: proposal.id,
+        action: proposal.action.clone(),
+        eta,
+        timestamp: now,
+    });
+    
+    Ok(())
+}
+
+// Marks an approved proposal executed, for actions carried out by their own instruction
+fn execute_approved_proposal(proposal: &mut Proposal, multisig: &Multisig) -> Result<()> {
+    require!(!proposal.executed, GameError::ProposalAlreadyExecuted);
+    require!(
+        multisig.approval_count(&proposal.approvals) >= multisig.threshold as usize,
+        GameError::ProposalThresholdNotMet
+    );
+    proposal.executed = true;
+    Ok(())
+}
+
+// Checks a max bet change against the multisig: lowering (or keeping) the limit needs no proposal,
+// raising it consumes an approved RaiseMaxBet proposal for exactly this mint and amount
+fn approve_max_bet_raise(
+    multisig: Option<&Multisig>,
+    proposal: Option<&mut Proposal>,
+    mint: Option<Pubkey>,
+    current_max_bet_amount: u64,
+    max_bet_amount: u64,
+    executor: Pubkey,
+) -> Result<()> {
+    if max_bet_amount <= current_max_bet_amount {
+        return Ok(());
+    }
+    
+    let (Some(multisig), Some(proposal)) = (multisig, proposal) else {
+        return Err(GameError::BetLimitRaiseNotApproved.into());
+    };
+    require!(
+        proposal.action == ProposalAction::RaiseMaxBet { mint, max_bet_amount },
+        GameError::InvalidProposalAction
+    );
+    execute_approved_proposal(proposal, multisig)?;
+    
+    emit!(ProposalExecuted {
+        proposal_id: proposal.id,
+        executor,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+// Only the defined pause bits can be set, there is no bit for refund paths
+pub fn validate_pause_flags(paused_flags: u8) -> Result<()> {
+    require!(paused_flags & !PAUSE_ALL == 0, GameError::InvalidPauseFlags);
+    Ok(())
+}
+
+// Validates a queued config change against the protocol bounds
+pub fn validate_config_change(change: &ConfigChange) -> Result<()> {
+    match change {
+        ConfigChange::CommissionRates { commission_bps, referrer_share_bps } => {
+            require!(*commission_bps <= MAX_COMMISSION_BPS, GameError::CommissionRateTooHigh);
+            require!(*referrer_share_bps <= BPS_DENOMINATOR, GameError::InvalidReferrerShare);
+        },
+        ConfigChange::TimeoutBounds { min_timeout_seconds, max_timeout_seconds } => {
+            require!(
+                *min_timeout_seconds > 0
+                    && min_timeout_seconds <= max_timeout_seconds
+                    && *max_timeout_seconds <= MAX_TIMEOUT_LIMIT_SECONDS,
+                GameError::InvalidTimeoutBounds
+            );
+        },
+        ConfigChange::TimelockDelay { delay_seconds } => {
+            require!(
+                (MIN_TIMELOCK_DELAY_SECONDS..=MAX_TIMELOCK_DELAY_SECONDS).contains(delay_seconds),
+                GameError::InvalidTimelockDelay
+            );
+        },
+        ConfigChange::SpectatorTerms { commission_bps, cutoff_seconds } => {
+            require!(*commission_bps <= MAX_COMMISSION_BPS, GameError::CommissionRateTooHigh);
+            require!(
+                *cutoff_seconds > 0 && *cutoff_seconds <= MAX_TIMEOUT_LIMIT_SECONDS,
+                GameError::InvalidTimeoutBounds
+            );
+        },
+        ConfigChange::BonusCommission { commission_bps } => {
+            require!(*commission_bps <= MAX_COMMISSION_BPS, GameError::CommissionRateTooHigh);
+        },
+    }
+    Ok(())
+}
+
+// Validates a signer set (multisig or oracles): non-empty, bounded, unique, and a reachable threshold
+fn validate_signer_set(signers: &[Pubkey], threshold: u8, max_signers: usize, error: GameError) -> Result<()> {
+    let valid = !signers.is_empty()
+        && signers.len() <= max_signers
+        && threshold > 0
+        && threshold as usize <= signers.len()
+        && signers.iter().enumerate().all(|(i, signer)| {
+            *signer != Pubkey::default() && !signers[..i].contains(signer)
+        });
+    if !valid {
+        return Err(error.into());
+    }
+    Ok(())
+}
+
+// Commission at `commission_bps` of `amount`, rounded down
+pub fn calculate_commission(amount: u64, commission_bps: u16) -> Result<u64> {
+    let commission = (amount as u128)
+        .checked_mul(commission_bps as u128).ok_or(GameError::ArithmeticOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128).ok_or(GameError::ArithmeticOverflow)?;
+    u64::try_from(commission).map_err(|_| GameError::ArithmeticOverflow.into())
+}
+
+pub fn validate_treasury_recipients(recipients: &[TreasuryRecipient]) -> Result<()> {
+    require!(
+        !recipients.is_empty() && recipients.len() <= MAX_TREASURY_RECIPIENTS,
+        GameError::InvalidTreasuryRecipients
+    );
+    
+    let mut total_weight_bps: u32 = 0;
+    for (i, recipient) in recipients.iter().enumerate() {
+        require!(recipient.recipient != Pubkey::default(), GameError::InvalidTreasuryRecipients);
+        require!(recipient.weight_bps > 0, GameError::InvalidTreasuryRecipients);
+        // Reject duplicate recipients
+        require!(
+            !recipients[..i].iter().any(|other| other.recipient == recipient.recipient),
+            GameError::InvalidTreasuryRecipients
+        );
+        total_weight_bps += recipient.weight_bps as u32;
+    }
+    // Weights must split the whole balance
+    require!(total_weight_bps == BPS_DENOMINATOR as u32, GameError::InvalidTreasuryRecipients);
+    
+    Ok(())
+}
+
+// Each recipient's cut of `distributable` by weight. The rounding remainder stays in the ledger for the next
+// distribution, so the shares never add up to more than the ledger holds.
+pub fn treasury_shares(distributable: u64, recipients: &[TreasuryRecipient]) -> Result<Vec<u64>> {
+    recipients
+        .iter()
+        .map(|recipient| calculate_commission(distributable, recipient.weight_bps))
+        .collect()
+}
+
+// Completes a lobby and pays its pool out to `winners` by `shares_bps`, with commission taken once from
+// the whole pool. Rounding remainder goes to the first winner. Returns the prize paid to each winner.
+fn settle_pool<'info>(
+    accounts: &mut SettleLobby<'info>,
+    payout_accounts: &[AccountInfo<'info>],
+    vault_bump: u8,
+    winners: &[Pubkey],
+    shares_bps: &[u16],
+) -> Result<Vec<u64>> {
+    // Payout destinations: wallets for SOL lobbies, their token accounts for SPL lobbies
+    require!(payout_accounts.len() == winners.len(), GameError::InvalidPayoutAccount);
+    
+    let lobby = &mut accounts.lobby;
+    
+    // Atomically update lobby state to prevent race conditions
+    lobby.winner = Some(winners[0]);
+    lobby.status = LobbyStatus::Completed;
+    lobby.completed_at = Some(Clock::get()?.unix_timestamp);
+    
+    let lobby_key = lobby.key();
+    let vault_bump = [vault_bump];
+    let vault_seeds: &[&[u8]] = &[b"vault", lobby_key.as_ref(), &vault_bump];
+    let lobby_vault = LobbyVault {
+        vault: &accounts.vault,
+        signer_seeds: vault_seeds,
+        token: vault_token(lobby.mint, &accounts.mint, &accounts.vault_token_account, &accounts.token_program)?,
+    };
+    
+    // Prize pool is what the vault actually holds (excluding rent-exempt amount)
+    let total_pool = lobby_vault.stake_balance()?;
+    let rent_exempt_amount = Rent::get()?.minimum_balance(0);
+    
+    // Commission is taken once from the whole pool at the rate snapshotted when the lobby was created
+    let (total_commission, prizes) = lobby.settlement(total_pool, shares_bps)?;
+    let credited_commission = take_commission(
+        &lobby_vault,
+        total_commission,
+        lobby.referrer_share_bps,
+        lobby.referrer,
+        &accounts.commission_vault,
+        &accounts.commission_token_vault,
+        &accounts.referrer,
+        &accounts.referrer_token_account,
+    )?;
+    
+    // Store our commission amount in contract state for tracking, per asset
+    accounts.contract_state.credit_commission(lobby.mint, credited_commission)?;
+    
+    for ((winner, prize), destination) in winners.iter().zip(prizes.iter()).zip(payout_accounts.iter()) {
+        let winner_destination = lobby_vault.player_payout_account(*winner, destination)?;
+        lobby_vault.transfer_out(&winner_destination, *prize)?;
+    }
+    for entry in lobby.players.iter_mut().filter(|entry| winners.contains(&entry.player)) {
+        entry.claimed = true;
+    }
+    
+    // Ensure vault retains rent-exempt status
+    let remaining_balance = accounts.vault.lamports();
+    require!(remaining_balance >= rent_exempt_amount, GameError::InsufficientVaultBalance);
+    
+    Ok(prizes)
+}
+
+// Pays `total_commission` out of a pool vault, with the referrer's share going to the referrer when it can
+// receive it. Returns the part credited to the commission vault.
+#[allow(clippy::too_many_arguments)]
+fn take_commission<'info>(
+    pool_vault: &LobbyVault<'_, 'info>,
+    total_commission: u64,
+    referrer_share_bps: u16,
+    pool_referrer: Option<Pubkey>,
+    commission_vault: &AccountInfo<'info>,
+    commission_token_vault: &Option<InterfaceAccount<'info, TokenAccount>>,
+    referrer: &Option<AccountInfo<'info>>,
+    referrer_token_account: &Option<InterfaceAccount<'info, TokenAccount>>,
+) -> Result<u64> {
+    let (our_commission, referrer_commission) = split_commission(
+        total_commission,
+        referrer_share_bps,
+        pool_referrer.is_some(),
+    )?;
+    
+    let commission_destination = pool_vault.commission_account(commission_vault, commission_token_vault)?;
+    let credited_commission = pool_vault.pay_commission(
+        &commission_destination,
+        pool_referrer,
+        referrer,
+        referrer_token_account,
+        our_commission,
+        referrer_commission,
+    )?;
+    
+    Ok(credited_commission)
+}
+
+// Commission at `commission_bps` of the whole pool, with the rest split by `shares_bps`.
+// Returns (total_commission, prizes), which always add up to `total_pool`.
+pub fn pool_payouts(total_pool: u64, commission_bps: u16, shares_bps: &[u16]) -> Result<(u64, Vec<u64>)> {
+    let total_commission = calculate_commission(total_pool, commission_bps)?;
+    let prize_after_commission = total_pool.checked_sub(total_commission).ok_or(GameError::ArithmeticOverflow)?;
+    let prizes = split_prize(prize_after_commission, shares_bps)?;
+    Ok((total_commission, prizes))
+}
+
+// Each share of the prize is rounded down, the rounding remainder goes to the first share
+pub fn split_prize(prize: u64, shares_bps: &[u16]) -> Result<Vec<u64>> {
+    require!(!shares_bps.is_empty(), GameError::InvalidPayoutTable);
+    let mut prizes = shares_bps
+        .iter()
+        .map(|share_bps| calculate_commission(prize, *share_bps))
+        .collect::<Result<Vec<u64>>>()?;
+    let paid_out = prizes.iter().try_fold(0u64, |total, prize| total.checked_add(*prize))
+        .ok_or(GameError::ArithmeticOverflow)?;
+    prizes[0] = prizes[0]
+        .checked_add(prize.checked_sub(paid_out).ok_or(GameError::ArithmeticOverflow)?)
+        .ok_or(GameError::ArithmeticOverflow)?;
+    
+    Ok(prizes)
+}
+
+// Closes the emptied token vault of SPL lobbies and tournaments and drains the lamport vault, both back to
+// `recipient`
+fn close_pool_vaults<'info>(
+    pool_mint: Option<Pubkey>,
+    vault: &AccountInfo<'info>,
+    vault_seeds: &[&[u8]],
+    recipient: &Signer<'info>,
+    mint: &Option<InterfaceAccount<'info, Mint>>,
+    vault_token_account: &Option<InterfaceAccount<'info, TokenAccount>>,
+    token_program: &Option<Interface<'info, TokenInterface>>,
+) -> Result<()> {
+    // SPL pools also hold an emptied token vault, closed back to the recipient with the vault PDA signing
+    if let Some(vault_token_account) = vault_token_account {
+        require!(vault_token_account.amount == 0, GameError::VaultNotEmpty);
+        let token_program = token_program.as_ref().ok_or(GameError::MissingTokenAccounts)?;
+        
+        // Transfer fees withheld on deposits block closing, so sweep them to the mint first
+        if withheld_transfer_fees(vault_token_account)? > 0 {
+            let mint = mint.as_ref().ok_or(GameError::MissingTokenAccounts)?;
+            require!(Some(mint.key()) == pool_mint, GameError::InvalidMint);
+            token_interface::harvest_withheld_tokens_to_mint(
+                CpiContext::new(
+                    token_program.to_account_info(),
+                    HarvestWithheldTokensToMint {
+                        token_program_id: token_program.to_account_info(),
+                        mint: mint.to_account_info(),
+                    },
+                ),
+                vec![vault_token_account.to_account_info()],
+            )?;
+        }
+        
+        token_interface::close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: vault_token_account.to_account_info(),
+                destination: recipient.to_account_info(),
+                authority: vault.clone(),
+            },
+            &[vault_seeds],
+        ))?;
+    } else {
+        require!(pool_mint.is_none(), GameError::MissingTokenAccounts);
+    }
+    
+    // Close vault manually since we can't use close attribute on AccountInfo
+    let vault_balance = vault.lamports();
+    vault.sub_lamports(vault_balance)?;
+    recipient.add_lamports(vault_balance)?;
+    
+    Ok(())
+}
+
+// Stake limits shared by lobbies and tournaments
+// SOL max bet and tier list, the tiers are the only amounts a SOL lobby can then be created with
+pub fn validate_bet_limits(max_bet_amount: u64, bet_tiers: &[u64]) -> Result<()> {
+    require!(max_bet_amount >= MIN_BET_AMOUNT, GameError::InvalidBetLimits);
+    require!(bet_tiers.len() <= MAX_BET_TIERS, GameError::InvalidBetLimits);
+    // Tiers must be strictly increasing and within the min/max range
+    require!(
+        bet_tiers.windows(2).all(|pair| pair[0] < pair[1]),
+        GameError::InvalidBetLimits
+    );
+    require!(
+        bet_tiers.iter().all(|tier| (MIN_BET_AMOUNT..=max_bet_amount).contains(tier)),
+        GameError::InvalidBetLimits
+    );
+    Ok(())
+}
+
+pub fn validate_stake_amount(
+    amount: u64,
+    is_token: bool,
+    stake_mint: Option<&StakeMint>,
+    global_config: &GlobalConfig,
+) -> Result<()> {
+    if is_token {
+        // Only allowlisted mints can be staked, with limits in the mint's own base units
+        let stake_mint = stake_mint.ok_or(GameError::MintNotAllowed)?;
+        require!(stake_mint.enabled, GameError::MintNotAllowed);
+        require!(amount >= stake_mint.min_bet_amount, GameError::BetAmountTooSmall);
+        require!(amount <= stake_mint.max_bet_amount, GameError::BetAmountTooLarge);
+    } else {
+        // Bet limits are in lamports and only apply to SOL stakes
+        require!(amount >= MIN_BET_AMOUNT, GameError::BetAmountTooSmall);
+        require!(amount <= global_config.max_bet_amount, GameError::BetAmountTooLarge);
+        // Empty tier list means any amount between min and max is allowed
+        require!(
+            global_config.bet_tiers.is_empty() || global_config.bet_tiers.contains(&amount),
+            GameError::BetAmountNotInTiers
+        );
+    }
+    Ok(())
+}
+
+// Payout table in basis points per place, best place first
+fn validate_payout_table(payout_bps: &[u16], max_players: u8) -> Result<()> {
+    require!(payout_bps.len() <= max_players as usize, GameError::InvalidPayoutTable);
+    // Every paid place gets something and a better place never gets less
+    require!(payout_bps.iter().all(|share_bps| *share_bps > 0), GameError::InvalidPayoutTable);
+    require!(payout_bps.windows(2).all(|pair| pair[0] >= pair[1]), GameError::InvalidPayoutTable);
+    
+    let total_bps = payout_bps.iter().map(|share_bps| *share_bps as u32).sum::<u32>();
+    require!(total_bps == BPS_DENOMINATOR as u32, GameError::InvalidPayoutTable);
+    
+    Ok(())
+}
+
+// Commission on stakes returned after a draw or timeout: each player is charged their share rounded up,
+// while only the part that divides evenly between players is paid out, so rounding never leaves the
+// vault short. Returns (payable_commission, commission_per_player).
+pub fn refund_commission(total_commission: u64, player_count: usize) -> Result<(u64, u64)> {
+    let player_count = player_count as u64;
+    let remainder = total_commission.checked_rem(player_count).ok_or(GameError::ArithmeticOverflow)?;
+    let payable_commission = total_commission.checked_sub(remainder).ok_or(GameError::ArithmeticOverflow)?;
+    let commission_per_player = total_commission.div_ceil(player_count);
+    Ok((payable_commission, commission_per_player))
+}
+
+// Splits commission into (our_commission, referrer_commission); rounding remainder stays with us
+pub fn split_commission(commission: u64, referrer_share_bps: u16, has_referrer: bool) -> Result<(u64, u64)> {
+    if !has_referrer {
+        return Ok((commission, 0));
+    }
+    let referrer_commission = calculate_commission(commission, referrer_share_bps)?;
+    let our_commission = commission.checked_sub(referrer_commission).ok_or(GameError::ArithmeticOverflow)?;
+    Ok((our_commission, referrer_commission))
+}
+
+// Token accounts backing an SPL lobby vault
+pub struct VaultToken<'a, 'info> {
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub vault_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+// Resolves the token accounts of an SPL lobby; SOL lobbies (no mint) resolve to None
+fn vault_token<'a, 'info>(
+    lobby_mint: Option<Pubkey>,
+    mint: &'a Option<InterfaceAccount<'info, Mint>>,
+    vault_token_account: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+    token_program: &'a Option<Interface<'info, TokenInterface>>,
+) -> Result<Option<VaultToken<'a, 'info>>> {
+    let Some(lobby_mint) = lobby_mint else {
+        return Ok(None);
+    };
+    let mint = mint.as_ref().ok_or(GameError::MissingTokenAccounts)?;
+    let vault_token_account = vault_token_account.as_ref().ok_or(GameError::MissingTokenAccounts)?;
+    let token_program = token_program.as_ref().ok_or(GameError::MissingTokenAccounts)?;
+    
+    require!(mint.key() == lobby_mint, GameError::InvalidMint);
+    
+    Ok(Some(VaultToken { mint, vault_token_account, token_program }))
+}
+
+// Moves a stake into a lobby vault: lamports for SOL lobbies, a token transfer for SPL lobbies.
+// Returns the amount the vault actually received, which is less than `amount` for transfer-fee mints.
+fn transfer_to_vault<'info>(
+    depositor: &Signer<'info>,
+    vault: &AccountInfo<'info>,
+    token: Option<&VaultToken<'_, 'info>>,
+    depositor_token_account: &Option<InterfaceAccount<'info, TokenAccount>>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<u64> {
+    match token {
+        None => {
+            let cpi_context = CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: depositor.to_account_info(),
+                    to: vault.clone(),
+                },
+            );
+            anchor_lang::system_program::transfer(cpi_context, amount)?;
+            Ok(amount)
+        },
+        Some(token) => {
+            let depositor_token_account = depositor_token_account.as_ref().ok_or(GameError::MissingTokenAccounts)?;
+            let balance_before = token_account_amount(token.vault_token_account)?;
+            let cpi_context = CpiContext::new(
+                token.token_program.to_account_info(),
+                TransferChecked {
+                    from: depositor_token_account.to_account_info(),
+                    mint: token.mint.to_account_info(),
+                    to: token.vault_token_account.to_account_info(),
+                    authority: depositor.to_account_info(),
+                },
+            );
+            token_interface::transfer_checked(cpi_context, amount, token.mint.decimals)?;
+            
+            let balance_after = token_account_amount(token.vault_token_account)?;
+            Ok(balance_after.checked_sub(balance_before).ok_or(GameError::ArithmeticOverflow)?)
+        },
+    }
+}
+
+// Token account balance read from live data, since earlier CPIs in this instruction may have moved tokens
+fn token_account_amount(token_account: &InterfaceAccount<TokenAccount>) -> Result<u64> {
+    let token_account_info = token_account.to_account_info();
+    let data = token_account_info.try_borrow_data()?;
+    Ok(TokenAccount::try_deserialize(&mut &data[..])?.amount)
+}
+
+// Transfer fees withheld in a Token-2022 account, these must be harvested before it can be closed
+fn withheld_transfer_fees(token_account: &InterfaceAccount<TokenAccount>) -> Result<u64> {
+    let token_account_info = token_account.to_account_info();
+    if *token_account_info.owner != Token2022::id() {
+        return Ok(0);
+    }
+    let data = token_account_info.try_borrow_data()?;
+    let account = StateWithExtensions::<SplAccount>::unpack(&data)?;
+    Ok(account.get_extension::<TransferFeeAmount>().map_or(0, |fee| u64::from(fee.withheld_amount)))
+}
+
+// Rejects Token-2022 mints with extensions that could move, freeze or hook tokens held in a vault
+fn validate_mint_extensions(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != Token2022::id() {
+        return Ok(());
+    }
+    let data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<SplMint>::unpack(&data)?;
+    for extension in mint_state.get_extension_types()? {
+        require!(SUPPORTED_MINT_EXTENSIONS.contains(&extension), GameError::UnsupportedMintExtension);
+    }
+    Ok(())
+}
+
+// Pays out of a lobby vault: lamports for SOL lobbies, transfers signed by the vault PDA for SPL lobbies
+pub struct LobbyVault<'a, 'info> {
+    pub vault: &'a AccountInfo<'info>,
+    pub signer_seeds: &'a [&'a [u8]],
+    pub token: Option<VaultToken<'a, 'info>>,
+}
+
+impl<'a, 'info> LobbyVault<'a, 'info> {
+    // Stake funds held by the vault, excluding the rent-exempt reserve of the lamport vault
+    pub fn stake_balance(&self) -> Result<u64> {
+        match &self.token {
+            None => {
+                let rent_exempt_amount = Rent::get()?.minimum_balance(0);
+                Ok(self.vault.lamports().saturating_sub(rent_exempt_amount))
+            },
+            Some(token) => token_account_amount(token.vault_token_account),
+        }
+    }
+
+    pub fn transfer_out(&self, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        match &self.token {
+            None => {
+                self.vault.sub_lamports(amount)?;
+                to.add_lamports(amount)?;
+                Ok(())
+            },
+            Some(token) => {
+                let signer_seeds = [self.signer_seeds];
+                let cpi_context = CpiContext::new_with_signer(
+                    token.token_program.to_account_info(),
+                    TransferChecked {
+                        from: token.vault_token_account.to_account_info(),
+                        mint: token.mint.to_account_info(),
+                        to: to.clone(),
+                        authority: self.vault.clone(),
+                    },
+                    &signer_seeds,
+                );
+                token_interface::transfer_checked(cpi_context, amount, token.mint.decimals)
+            },
+        }
+    }
+
+    // Account receiving a player payout: the wallet itself for SOL lobbies, its token account for SPL lobbies
+    pub fn payout_account(
+        &self,
+        wallet: &AccountInfo<'info>,
+        token_account: &Option<InterfaceAccount<'info, TokenAccount>>,
+    ) -> Result<AccountInfo<'info>> {
+        match &self.token {
+            None => Ok(wallet.clone()),
+            Some(token) => {
+                let token_account = token_account.as_ref().ok_or(GameError::MissingTokenAccounts)?;
+                require!(token_account.mint == token.mint.key(), GameError::InvalidMint);
+                require!(token_account.owner == wallet.key(), GameError::InvalidTokenAccountOwner);
+                Ok(token_account.to_account_info())
+            },
+        }
+    }
+
+    // Payout account passed through remaining_accounts for `wallet`: the wallet itself for SOL lobbies,
+    // a token account it owns for SPL lobbies
+    pub fn player_payout_account(&self, wallet: Pubkey, destination: &AccountInfo<'info>) -> Result<AccountInfo<'info>> {
+        match &self.token {
+            None => {
+                require!(destination.key() == wallet, GameError::InvalidPayoutAccount);
+            },
+            Some(token) => {
+                let data = destination.try_borrow_data()?;
+                let token_account = TokenAccount::try_deserialize(&mut &data[..])?;
+                require!(token_account.mint == token.mint.key(), GameError::InvalidMint);
+                require!(token_account.owner == wallet, GameError::InvalidTokenAccountOwner);
+            },
+        }
+        Ok(destination.clone())
+    }
+
+    // Commission destination: the SOL commission vault, or the mint's commission token vault
+    pub fn commission_account(
+        &self,
+        commission_vault: &AccountInfo<'info>,
+        commission_token_vault: &Option<InterfaceAccount<'info, TokenAccount>>,
+    ) -> Result<AccountInfo<'info>> {
+        match &self.token {
+            None => Ok(commission_vault.clone()),
+            Some(token) => {
+                let commission_token_vault = commission_token_vault.as_ref().ok_or(GameError::MissingTokenAccounts)?;
+                require!(commission_token_vault.mint == token.mint.key(), GameError::InvalidMint);
+                Ok(commission_token_vault.to_account_info())
+            },
+        }
+    }
+
+    // Pays our commission to the commission vault and the referrer's share to the referrer when it can
+    // receive it, otherwise to us as well. Returns the amount the commission vault actually received,
+    // which is less than what was sent for transfer-fee mints.
+    pub fn pay_commission(
+        &self,
+        commission_destination: &AccountInfo<'info>,
+        lobby_referrer: Option<Pubkey>,
+        referrer: &Option<AccountInfo<'info>>,
+        referrer_token_account: &Option<InterfaceAccount<'info, TokenAccount>>,
+        our_commission: u64,
+        referrer_commission: u64,
+    ) -> Result<u64> {
+        let balance_before = self.destination_balance(commission_destination)?;
+        self.transfer_out(commission_destination, our_commission)?;
+        
+        if let Some(referrer_key) = lobby_referrer {
+            match self.referrer_account(referrer_key, referrer, referrer_token_account, referrer_commission)? {
+                Some(referrer_destination) => {
+                    self.transfer_out(&referrer_destination, referrer_commission)?;
+                },
+                None => {
+                    // Add referrer's commission to our commission (safer fallback)
+                    self.transfer_out(commission_destination, referrer_commission)?;
+                },
+            }
+        }
+        
+        let balance_after = self.destination_balance(commission_destination)?;
+        Ok(balance_after.checked_sub(balance_before).ok_or(GameError::ArithmeticOverflow)?)
+    }
+
+    // Balance of a payout destination read from live data: lamports for SOL lobbies, tokens for SPL lobbies
+    fn destination_balance(&self, destination: &AccountInfo<'info>) -> Result<u64> {
+        match &self.token {
+            None => Ok(destination.lamports()),
+            Some(_) => {
+                let data = destination.try_borrow_data()?;
+                Ok(TokenAccount::try_deserialize(&mut &data[..])?.amount)
+            },
+        }
+    }
+
+    // Referrer destination, or None if no account was provided or it can't safely receive funds
+    fn referrer_account(
+        &self,
+        referrer_key: Pubkey,
+        referrer: &Option<AccountInfo<'info>>,
+        referrer_token_account: &Option<InterfaceAccount<'info, TokenAccount>>,
+        referrer_commission: u64,
+    ) -> Result<Option<AccountInfo<'info>>> {
+        match &self.token {
+            None => {
+                let Some(referrer_account) = referrer else {
+                    return Ok(None);
+                };
+                // Validate referrer account matches the one stored in lobby
+                require!(referrer_account.key() == referrer_key, GameError::InvalidReferrer);
+                
+                // Only transfer if referrer account stays rent-exempt after receiving commission
+                let rent_exempt_minimum = Rent::get()?.minimum_balance(0);
+                if referrer_account.lamports() > 0 || referrer_commission >= rent_exempt_minimum {
+                    Ok(Some(referrer_account.clone()))
+                } else {
+                    Ok(None)
+                }
+            },
+            Some(token) => {
+                let Some(referrer_token_account) = referrer_token_account else {
+                    return Ok(None);
+                };
+                require!(referrer_token_account.owner == referrer_key, GameError::InvalidReferrer);
+                require!(referrer_token_account.mint == token.mint.key(), GameError::InvalidMint);
+                Ok(Some(referrer_token_account.to_account_info()))
+            },
+        }
+    }
+}
 
 // Ed25519 signature verification helper function
-fn verify_ed25519_signature(
+// Accepts a single Ed25519 precompile instruction carrying one signature per oracle over the same message
+fn verify_oracle_signatures(
     instruction_sysvar: &AccountInfo,
-    expected_public_key: &[u8],
+    global_config: &GlobalConfig,
+    message: &[u8],
+    signatures: &[[u8; 64]],
+) -> Result<()> {
+    let instruction_data = load_ed25519_instruction(instruction_sysvar)?;
+    check_oracle_signatures(&instruction_data, global_config, message, signatures)
+}
+
+// Checks the Ed25519 instruction data carries `signatures` over `message` from at least threshold distinct oracles
+pub fn check_oracle_signatures(
+    instruction_data: &[u8],
+    global_config: &GlobalConfig,
     message: &[u8],
-    signature: &[u8],
+    signatures: &[[u8; 64]],
 ) -> Result<()> {
+    let (num_signatures, data_start) = ed25519_header(instruction_data, MAX_ORACLES)?;
+    require!(num_signatures == signatures.len(), GameError::InvalidSignature);
+
+    let mut signed_oracles: Vec<Pubkey> = Vec::with_capacity(num_signatures);
+    for (i, signature) in signatures.iter().enumerate() {
+        let (oracle, signed_message, signed_signature) = ed25519_signature(instruction_data, data_start, i)?;
+        
+        // Verify public key is a registered oracle that hasn't signed yet
+        require!(global_config.oracles.contains(&oracle), GameError::UnknownOracle);
+        require!(!signed_oracles.contains(&oracle), GameError::DuplicateOracleSignature);
+        
+        require!(signed_message == message, GameError::InvalidSignature);
+        require!(signed_signature == signature, GameError::InvalidSignature);
+
+        signed_oracles.push(oracle);
+    }
+
+    require!(signed_oracles.len() >= global_config.oracle_threshold as usize, GameError::OracleThresholdNotMet);
+
+    // If we reach here, Ed25519 precompile has successfully verified every signature
+    Ok(())
+}
+
+// Verifies the Ed25519 instruction before this one holds a single signature by `invite_key` over `message`
+fn verify_invite_signature(instruction_sysvar: &AccountInfo, invite_key: &Pubkey, message: &[u8]) -> Result<()> {
+    let instruction_data = load_ed25519_instruction(instruction_sysvar)?;
+    check_invite_signature(&instruction_data, invite_key, message)
+}
+
+// Checks the Ed25519 instruction data holds a single signature by `invite_key` over `message`
+pub fn check_invite_signature(instruction_data: &[u8], invite_key: &Pubkey, message: &[u8]) -> Result<()> {
+    let (num_signatures, data_start) = ed25519_header(instruction_data, 1)?;
+    require!(num_signatures == 1, GameError::InvalidInviteSignature);
+    
+    let (signer, signed_message, _) = ed25519_signature(instruction_data, data_start, 0)?;
+    require!(signer == *invite_key && signed_message == message, GameError::InvalidInviteSignature);
+    
+    // Ed25519 precompile has already verified the signature itself
+    Ok(())
+}
+
+// Loads the data of the Ed25519 precompile instruction placed right before the current one
+fn load_ed25519_instruction(instruction_sysvar: &AccountInfo) -> Result<Vec<u8>> {
     let current_index = load_current_index_checked(instruction_sysvar)?;
     require!(current_index > 0, GameError::InvalidSignature);
 
-    // Safe conversion: current_index is guaranteed > 0 due to check above
-    let ed25519_instruction = load_instruction_at_checked(
-        current_index.saturating_sub(1) as usize, 
-        instruction_sysvar
-    )?;
+    // Safe conversion: current_index is guaranteed > 0 due to check above
+    let ed25519_instruction = load_instruction_at_checked(
+        current_index.saturating_sub(1) as usize, 
+        instruction_sysvar
+    )?;
+    
+    // Verify this is actually an Ed25519 instruction
+    require!(ed25519_instruction.program_id == solana_program::ed25519_program::ID, GameError::InvalidSignature);
+    
+    Ok(ed25519_instruction.data)
+}
+
+// Returns the number of signatures the Ed25519 instruction data carries and where the signed data starts
+fn ed25519_header(instruction_data: &[u8], max_signatures: usize) -> Result<(usize, usize)> {
+    require!(instruction_data.len() >= SIGNATURE_OFFSETS_START, GameError::InvalidSignature);
+
+    let num_signatures = instruction_data[0] as usize;
+    require!(num_signatures > 0 && num_signatures <= max_signatures, GameError::InvalidSignature);
+    // instruction_data[1] is padding byte, ignore
+
+    // Check full structure size: 2 bytes header + 14 bytes Ed25519SignatureOffsets per signature
+    let data_start = SIGNATURE_OFFSETS_START
+        .checked_add(num_signatures.checked_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE).ok_or(GameError::InvalidSignature)?)
+        .ok_or(GameError::InvalidSignature)?;
+    require!(instruction_data.len() >= data_start, GameError::InvalidSignature);
+
+    Ok((num_signatures, data_start))
+}
+
+// Parses the `index`th Ed25519SignatureOffsets entry, which must point into the Ed25519 instruction itself.
+// Returns the signer's public key, the signed message and the signature.
+fn ed25519_signature(instruction_data: &[u8], data_start: usize, index: usize) -> Result<(Pubkey, &[u8], &[u8])> {
+    // Parse COMPLETE Ed25519SignatureOffsets structure (14 bytes per signature)
+    let offsets = &instruction_data[SIGNATURE_OFFSETS_START + index * SIGNATURE_OFFSETS_SERIALIZED_SIZE..];
+    let signature_offset = u16::from_le_bytes([offsets[0], offsets[1]]);
+    let signature_instruction_index = u16::from_le_bytes([offsets[2], offsets[3]]);
+    let public_key_offset = u16::from_le_bytes([offsets[4], offsets[5]]);
+    let public_key_instruction_index = u16::from_le_bytes([offsets[6], offsets[7]]);
+    let message_data_offset = u16::from_le_bytes([offsets[8], offsets[9]]);
+    let message_data_size = u16::from_le_bytes([offsets[10], offsets[11]]);
+    let message_instruction_index = u16::from_le_bytes([offsets[12], offsets[13]]);
+
+    require!(signature_instruction_index == u16::MAX, GameError::InvalidSignature);
+    require!(public_key_instruction_index == u16::MAX, GameError::InvalidSignature);
+    require!(message_instruction_index == u16::MAX, GameError::InvalidSignature);
+
+    // Public key with safe bounds checking
+    let pubkey_start = public_key_offset as usize;
+    let pubkey_end = pubkey_start.checked_add(PUBKEY_SERIALIZED_SIZE).ok_or(GameError::InvalidSignature)?;
+    require!(pubkey_start >= data_start, GameError::InvalidSignature);
+    require!(pubkey_end <= instruction_data.len(), GameError::InvalidSignature);
+    let signer = Pubkey::try_from(&instruction_data[pubkey_start..pubkey_end])
+        .map_err(|_| GameError::InvalidSignature)?;
+
+    // Message with safe bounds checking
+    let msg_start = message_data_offset as usize;
+    let msg_size = message_data_size as usize;
+    let msg_end = msg_start.checked_add(msg_size).ok_or(GameError::InvalidSignature)?;
+    require!(msg_start >= data_start, GameError::InvalidSignature);
+    require!(msg_end <= instruction_data.len(), GameError::InvalidSignature);
+
+    // Signature with safe bounds checking
+    let sig_start = signature_offset as usize;
+    let sig_end = sig_start.checked_add(SIGNATURE_SERIALIZED_SIZE).ok_or(GameError::InvalidSignature)?;
+    require!(sig_start >= data_start, GameError::InvalidSignature);
+    require!(sig_end <= instruction_data.len(), GameError::InvalidSignature);
+
+    Ok((signer, &instruction_data[msg_start..msg_end], &instruction_data[sig_start..sig_end]))
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ContractState::INIT_SPACE,
+        seeds = [b"contract_state"],
+        bump
+    )]
+    pub contract_state: Account<'info, ContractState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 0,
+        seeds = [b"commission_vault"],
+        bump
+    )]
+    /// CHECK: Commission vault PDA for storing commission funds
+    pub commission_vault: AccountInfo<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GlobalConfig::INIT_SPACE,
+        seeds = [b"global_config"],
+        bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ GameError::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::SnakeGame>,
+    
+    /// Only the program upgrade authority may initialize, which prevents front-running the config
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ GameError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ GameError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBetLimits<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ GameError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    /// Only needed to raise the max bet
+    #[account(
+        seeds = [b"multisig"],
+        bump = multisig.bump
+    )]
+    pub multisig: Option<Account<'info, Multisig>>,
+    
+    /// Approved RaiseMaxBet proposal, only needed to raise the max bet. Derived from the multisig like every
+    /// other proposal, so it can't be passed without it.
+    #[account(
+        mut,
+        seeds = [b"proposal", multisig.as_ref().map(|m| m.key()).unwrap_or_default().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QueueConfigChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ GameError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + QueuedConfigChange::INIT_SPACE,
+        seeds = [b"config_change".as_ref(), &global_config.config_change_count.to_le_bytes()],
+        bump
+    )]
+    pub queued_change: Account<'info, QueuedConfigChange>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteConfigChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        mut,
+        close = queued_by,
+        has_one = queued_by,
+        seeds = [b"config_change".as_ref(), &queued_change.id.to_le_bytes()],
+        bump = queued_change.bump
+    )]
+    pub queued_change: Account<'info, QueuedConfigChange>,
+    
+    /// CHECK: Original payer receiving the rent back (validated by has_one)
+    #[account(mut)]
+    pub queued_by: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelConfigChange<'info> {
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ GameError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        mut,
+        close = queued_by,
+        has_one = queued_by,
+        seeds = [b"config_change".as_ref(), &queued_change.id.to_le_bytes()],
+        bump = queued_change.bump
+    )]
+    pub queued_change: Account<'info, QueuedConfigChange>,
+    
+    /// CHECK: Original payer receiving the rent back (validated by has_one)
+    #[account(mut)]
+    pub queued_by: AccountInfo<'info>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddStakeMint<'info> {
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ GameError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + StakeMint::INIT_SPACE,
+        seeds = [b"stake_mint", mint.key().as_ref()],
+        bump
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    
+    #[account(
+        mut,
+        seeds = [b"contract_state"],
+        bump
+    )]
+    pub contract_state: Account<'info, ContractState>,
+    
+    #[account(
+        seeds = [b"commission_vault"],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is the global commission vault, owner of every commission token vault
+    pub commission_vault: AccountInfo<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"commission_token_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = commission_vault,
+        token::token_program = token_program
+    )]
+    pub commission_token_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"multisig"],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    /// Approved RaiseMaxBet proposal for this mint
+    #[account(
+        mut,
+        seeds = [b"proposal", multisig.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStakeMint<'info> {
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ GameError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake_mint", stake_mint.mint.as_ref()],
+        bump = stake_mint.bump
+    )]
+    pub stake_mint: Account<'info, StakeMint>,
+    
+    /// Only needed to raise the max bet
+    #[account(
+        seeds = [b"multisig"],
+        bump = multisig.bump
+    )]
+    pub multisig: Option<Account<'info, Multisig>>,
+    
+    /// Approved RaiseMaxBet proposal, only needed to raise the max bet
+    #[account(
+        mut,
+        seeds = [b"proposal", multisig.as_ref().map(|m| m.key()).unwrap_or_default().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Option<Account<'info, Proposal>>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMultisig<'info> {
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ GameError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Multisig::INIT_SPACE,
+        seeds = [b"multisig"],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&proposer.key()) @ GameError::NotMultisigSigner
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [b"proposal", multisig.key().as_ref(), &multisig.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    #[account(
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&approver.key()) @ GameError::NotMultisigSigner
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", multisig.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&executor.key()) @ GameError::NotMultisigSigner
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", multisig.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    #[account(
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&executor.key()) @ GameError::NotMultisigSigner
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    /// Proposal being cancelled
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [b"proposal", multisig.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    /// Approved CancelProposal proposal being executed
+    #[account(
+        mut,
+        seeds = [b"proposal", multisig.key().as_ref(), &cancellation.id.to_le_bytes()],
+        bump = cancellation.bump
+    )]
+    pub cancellation: Account<'info, Proposal>,
+    
+    /// CHECK: Original proposer receiving the rent back (validated by has_one)
+    #[account(mut)]
+    pub proposer: AccountInfo<'info>,
+    
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        constraint = global_config.pending_admin == Some(new_admin.key()) @ GameError::NotPendingAdmin
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    pub new_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(bet_amount: u64, lobby_id: String)]
+pub struct CreateLobby<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Lobby::INIT_SPACE,
+        seeds = [b"lobby", lobby_id.as_bytes()],
+        bump
+    )]
+    pub lobby: Account<'info, Lobby>,
+    
+    #[account(
+        init,
+        payer = creator,
+        space = 0,
+        seeds = [b"vault", lobby.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for storing bet funds
+    pub vault: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    /// Stake mint for SPL lobbies, omitted for SOL lobbies
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"token_vault", lobby.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault,
+        token::token_program = token_program
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub creator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Allowlist entry of the stake mint, omitted for SOL lobbies
+    #[account(
+        seeds = [b"stake_mint", mint.as_ref().map(|mint| mint.key()).unwrap_or_default().as_ref()],
+        bump = stake_mint.bump
+    )]
+    pub stake_mint: Option<Account<'info, StakeMint>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinLobby<'info> {
+    #[account(mut)]
+    pub lobby: Account<'info, Lobby>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", lobby.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is just a vault account
+    pub vault: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub opponent: Signer<'info>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    /// Stake mint, required for SPL lobbies (validated against lobby.mint)
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_vault", lobby.key().as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub opponent_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: This is the instruction sysvar
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instruction_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StartLobby<'info> {
+    #[account(
+        mut,
+        has_one = creator
+    )]
+    pub lobby: Account<'info, Lobby>,
+    
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(mut)]
+    pub lobby: Account<'info, Lobby>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", lobby.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is just a vault account
+    pub vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_vault"],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is the global commission vault
+    pub commission_vault: AccountInfo<'info>,
+    
+    /// The winner who is claiming the prize - must be a signer
+    #[account(mut)]
+    pub winner: Signer<'info>,
+    
+    /// CHECK: Optional referrer account to receive commission
+    pub referrer: Option<AccountInfo<'info>>,
+    
+    #[account(
+        mut,
+        seeds = [b"contract_state"],
+        bump
+    )]
+    pub contract_state: Account<'info, ContractState>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    /// CHECK: This is the instruction sysvar
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instruction_sysvar: AccountInfo<'info>,
+    
+    /// Stake mint, required for SPL lobbies (validated against lobby.mint)
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_vault", lobby.key().as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_token_vault", lobby.mint.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub commission_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub winner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub referrer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleLobby<'info> {
+    #[account(mut)]
+    pub lobby: Account<'info, Lobby>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", lobby.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is just a vault account
+    pub vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_vault"],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is the global commission vault
+    pub commission_vault: AccountInfo<'info>,
+    
+    /// Participant submitting the attested result, or conceding with forfeit
+    pub settler: Signer<'info>,
+    
+    /// CHECK: Optional referrer account to receive commission
+    pub referrer: Option<AccountInfo<'info>>,
+    
+    #[account(
+        mut,
+        seeds = [b"contract_state"],
+        bump
+    )]
+    pub contract_state: Account<'info, ContractState>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    /// CHECK: This is the instruction sysvar
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instruction_sysvar: AccountInfo<'info>,
+    
+    /// Stake mint, required for SPL lobbies (validated against lobby.mint)
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_vault", lobby.key().as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_token_vault", lobby.mint.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub commission_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub referrer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(tournament_id: String)]
+pub struct CreateTournament<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Tournament::INIT_SPACE,
+        seeds = [b"tournament", tournament_id.as_bytes()],
+        bump
+    )]
+    pub tournament: Account<'info, Tournament>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 0,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for storing entry fees
+    pub vault: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump,
+        has_one = admin @ GameError::Unauthorized
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    /// Entry fee mint for SPL tournaments, omitted for SOL tournaments
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"tournament_token_vault", tournament.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault,
+        token::token_program = token_program
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Allowlist entry of the entry fee mint, omitted for SOL tournaments
+    #[account(
+        seeds = [b"stake_mint", mint.as_ref().map(|mint| mint.key()).unwrap_or_default().as_ref()],
+        bump = stake_mint.bump
+    )]
+    pub stake_mint: Option<Account<'info, StakeMint>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinTournament<'info> {
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    
+    #[account(
+        mut,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is just a vault account
+    pub vault: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub entrant: Signer<'info>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    /// Entry fee mint, required for SPL tournaments (validated against tournament.mint)
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"tournament_token_vault", tournament.key().as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub entrant_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleTournament<'info> {
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    
+    #[account(
+        mut,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is just a vault account
+    pub vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_vault"],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is the global commission vault
+    pub commission_vault: AccountInfo<'info>,
+    
+    /// Entrant submitting the attested match result
+    pub settler: Signer<'info>,
+    
+    /// CHECK: Optional referrer account to receive commission
+    pub referrer: Option<AccountInfo<'info>>,
+    
+    #[account(
+        mut,
+        seeds = [b"contract_state"],
+        bump
+    )]
+    pub contract_state: Account<'info, ContractState>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    /// CHECK: This is the instruction sysvar
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instruction_sysvar: AccountInfo<'info>,
+    
+    /// Entry fee mint, required for SPL tournaments (validated against tournament.mint)
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"tournament_token_vault", tournament.key().as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_token_vault", tournament.mint.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub commission_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub referrer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTournament<'info> {
+    #[account(mut)]
+    pub tournament: Account<'info, Tournament>,
+    
+    #[account(
+        mut,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is just a vault account
+    pub vault: AccountInfo<'info>,
+    
+    pub canceller: Signer<'info>,
+    
+    /// Entry fee mint, required for SPL tournaments (validated against tournament.mint)
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"tournament_token_vault", tournament.key().as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTournament<'info> {
+    #[account(
+        mut,
+        close = organizer,
+        has_one = organizer
+    )]
+    pub tournament: Account<'info, Tournament>,
+    
+    #[account(
+        mut,
+        seeds = [b"tournament_vault", tournament.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is just a vault account
+    pub vault: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub organizer: Signer<'info>,
+    
+    /// Entry fee mint of SPL tournaments, receives withheld transfer fees before the token vault is closed
+    #[account(mut)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    /// Token vault of SPL tournaments, closed together with the tournament
+    #[account(
+        mut,
+        seeds = [b"tournament_token_vault", tournament.key().as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenSpectatorPool<'info> {
+    pub lobby: Account<'info, Lobby>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SpectatorPool::INIT_SPACE,
+        seeds = [b"spectator_pool", lobby.key().as_ref()],
+        bump
+    )]
+    pub spectator_pool: Account<'info, SpectatorPool>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 0,
+        seeds = [b"spectator_vault", lobby.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Vault PDA for storing spectator bets
+    pub spectator_vault: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceSpectatorBet<'info> {
+    pub lobby: Account<'info, Lobby>,
+    
+    #[account(
+        mut,
+        seeds = [b"spectator_pool", lobby.key().as_ref()],
+        bump = spectator_pool.bump,
+        has_one = lobby
+    )]
+    pub spectator_pool: Account<'info, SpectatorPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"spectator_vault", lobby.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is just a vault account
+    pub spectator_vault: AccountInfo<'info>,
+    
+    #[account(
+        init,
+        payer = spectator,
+        space = 8 + SpectatorBet::INIT_SPACE,
+        seeds = [b"spectator_bet", spectator_pool.key().as_ref(), spectator.key().as_ref()],
+        bump
+    )]
+    pub spectator_bet: Account<'info, SpectatorBet>,
+    
+    #[account(mut)]
+    pub spectator: Signer<'info>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleSpectatorPool<'info> {
+    pub lobby: Account<'info, Lobby>,
+    
+    #[account(
+        mut,
+        seeds = [b"spectator_pool", lobby.key().as_ref()],
+        bump = spectator_pool.bump,
+        has_one = lobby
+    )]
+    pub spectator_pool: Account<'info, SpectatorPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"spectator_vault", lobby.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is just a vault account
+    pub spectator_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_vault"],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is the global commission vault
+    pub commission_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"contract_state"],
+        bump
+    )]
+    pub contract_state: Account<'info, ContractState>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSpectatorPayout<'info> {
+    #[account(
+        mut,
+        seeds = [b"spectator_pool", spectator_pool.lobby.as_ref()],
+        bump = spectator_pool.bump
+    )]
+    pub spectator_pool: Account<'info, SpectatorPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"spectator_vault", spectator_pool.lobby.as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is just a vault account
+    pub spectator_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"spectator_bet", spectator_pool.key().as_ref(), spectator.key().as_ref()],
+        bump = spectator_bet.bump,
+        has_one = spectator,
+        close = spectator
+    )]
+    pub spectator_bet: Account<'info, SpectatorBet>,
+    
+    #[account(mut)]
+    pub spectator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSpectatorPool<'info> {
+    #[account(
+        mut,
+        seeds = [b"spectator_pool", spectator_pool.lobby.as_ref()],
+        bump = spectator_pool.bump,
+        has_one = opener,
+        close = opener
+    )]
+    pub spectator_pool: Account<'info, SpectatorPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"spectator_vault", spectator_pool.lobby.as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is just a vault account
+    pub spectator_vault: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub opener: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_vault"],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is the global commission vault
+    pub commission_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"contract_state"],
+        bump
+    )]
+    pub contract_state: Account<'info, ContractState>,
+}
+
+#[derive(Accounts)]
+pub struct SponsorLobby<'info> {
+    #[account(mut)]
+    pub lobby: Account<'info, Lobby>,
     
-    // Verify this is actually an Ed25519 instruction
-    require!(ed25519_instruction.program_id == solana_program::ed25519_program::ID, GameError::InvalidSignature);
+    #[account(
+        init,
+        payer = sponsor,
+        space = 8 + BonusContribution::INIT_SPACE,
+        seeds = [b"bonus", lobby.key().as_ref(), sponsor.key().as_ref()],
+        bump
+    )]
+    pub bonus_contribution: Account<'info, BonusContribution>,
     
-    // Verify the content of the Ed25519 instruction
-    let instruction_data = ed25519_instruction.data;
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
     
-    // Check full structure size: 2 bytes header + 14 bytes Ed25519SignatureOffsets
-    const FULL_HEADER_SIZE: usize = SIGNATURE_OFFSETS_START + SIGNATURE_OFFSETS_SERIALIZED_SIZE;
-    require!(instruction_data.len() >= FULL_HEADER_SIZE, GameError::InvalidSignature);
-
-    let num_signatures = instruction_data[0];
-    require!(num_signatures == 1, GameError::InvalidSignature);
-    // instruction_data[1] is padding byte, ignore
-
-    // Parse COMPLETE Ed25519SignatureOffsets structure (14 bytes starting from index 2)
-    let signature_offset = u16::from_le_bytes([instruction_data[2], instruction_data[3]]);
-    let signature_instruction_index = u16::from_le_bytes([instruction_data[4], instruction_data[5]]);
-    let public_key_offset = u16::from_le_bytes([instruction_data[6], instruction_data[7]]);
-    let public_key_instruction_index = u16::from_le_bytes([instruction_data[8], instruction_data[9]]);
-    let message_data_offset = u16::from_le_bytes([instruction_data[10], instruction_data[11]]);
-    let message_data_size = u16::from_le_bytes([instruction_data[12], instruction_data[13]]);
-    let message_instruction_index = u16::from_le_bytes([instruction_data[14], instruction_data[15]]);
-
-    require!(signature_instruction_index == u16::MAX, GameError::InvalidSignature);
-    require!(public_key_instruction_index == u16::MAX, GameError::InvalidSignature);
-    require!(message_instruction_index == u16::MAX, GameError::InvalidSignature);
-
-    let data_start = FULL_HEADER_SIZE;
-
-    // Verify public key with safe bounds checking
-    let pubkey_start = public_key_offset as usize;
-    let pubkey_end = pubkey_start.checked_add(PUBKEY_SERIALIZED_SIZE).ok_or(GameError::InvalidSignature)?;
-    require!(pubkey_start >= data_start, GameError::InvalidSignature);
-    require!(pubkey_end <= instruction_data.len(), GameError::InvalidSignature);
-    require!(&instruction_data[pubkey_start..pubkey_end] == expected_public_key, GameError::InvalidSignature);
-
-    // Verify message with safe bounds checking
-    let msg_start = message_data_offset as usize;
-    let msg_size = message_data_size as usize;
-    let msg_end = msg_start.checked_add(msg_size).ok_or(GameError::InvalidSignature)?;
-    require!(msg_start >= data_start, GameError::InvalidSignature);
-    require!(msg_end <= instruction_data.len(), GameError::InvalidSignature);
-    require!(&instruction_data[msg_start..msg_end] == message, GameError::InvalidSignature);
-
-    // Verify signature with safe bounds checking
-    let sig_start = signature_offset as usize;
-    let sig_end = sig_start.checked_add(SIGNATURE_SERIALIZED_SIZE).ok_or(GameError::InvalidSignature)?;
-    require!(sig_start >= data_start, GameError::InvalidSignature);
-    require!(sig_end <= instruction_data.len(), GameError::InvalidSignature);
-    require!(&instruction_data[sig_start..sig_end] == signature, GameError::InvalidSignature);
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    pub system_program: Program<'info, System>,
+}
 
-    // If we reach here, Ed25519 precompile has successfully verified the signature
-    Ok(())
+#[derive(Accounts)]
+pub struct SettleBonus<'info> {
+    #[account(mut)]
+    pub lobby: Account<'info, Lobby>,
+    
+    #[account(
+        mut,
+        seeds = [b"bonus", lobby.key().as_ref(), sponsor.key().as_ref()],
+        bump = bonus_contribution.bump,
+        has_one = lobby,
+        has_one = sponsor,
+        close = sponsor
+    )]
+    pub bonus_contribution: Account<'info, BonusContribution>,
+    
+    /// CHECK: Sponsor of the contribution, validated by has_one
+    #[account(mut)]
+    pub sponsor: AccountInfo<'info>,
+    
+    /// CHECK: Lobby winner, validated against lobby.winner; only needed once the lobby completed
+    #[account(mut)]
+    pub winner: Option<AccountInfo<'info>>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_vault"],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is the global commission vault
+    pub commission_vault: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"contract_state"],
+        bump
+    )]
+    pub contract_state: Account<'info, ContractState>,
 }
 
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+pub struct ClaimCommission<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + ContractState::INIT_SPACE,
+        mut,
         seeds = [b"contract_state"],
         bump
     )]
     pub contract_state: Account<'info, ContractState>,
     
     #[account(
-        init,
-        payer = authority,
-        space = 0,
+        mut,
         seeds = [b"commission_vault"],
-        bump
+        bump,
+        owner = crate::ID
     )]
-    /// CHECK: Commission vault PDA for storing commission funds
+    /// CHECK: This is the global commission vault
     pub commission_vault: AccountInfo<'info>,
     
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&executor.key()) @ GameError::NotMultisigSigner
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    /// Approved WithdrawCommission proposal being executed
     #[account(
         mut,
-        constraint = authority.key() == BACKEND_AUTHORITY
+        seeds = [b"proposal", multisig.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
     )]
-    pub authority: Signer<'info>,
+    pub proposal: Account<'info, Proposal>,
+    
+    /// Multisig signer executing the withdrawal
+    pub executor: Signer<'info>,
+    
+    /// CHECK: Receives the commission (validated against global_config.commission_claimer)
+    #[account(
+        mut,
+        constraint = commission_claimer.key() == global_config.commission_claimer @ GameError::InvalidCommissionClaimer
+    )]
+    pub commission_claimer: AccountInfo<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(bet_amount: u64, lobby_id: String, referrer: Option<Pubkey>)]
-pub struct CreateLobby<'info> {
+pub struct DistributeCommission<'info> {
     #[account(
-        init,
-        payer = creator,
-        space = 8 + Lobby::INIT_SPACE,
-        seeds = [b"lobby", lobby_id.as_bytes()],
+        mut,
+        seeds = [b"contract_state"],
         bump
     )]
-    pub lobby: Account<'info, Lobby>,
+    pub contract_state: Account<'info, ContractState>,
     
     #[account(
-        init,
-        payer = creator,
-        space = 0,
-        seeds = [b"vault", lobby.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"commission_vault"],
+        bump,
+        owner = crate::ID
     )]
-    /// CHECK: Vault PDA for storing bet funds
-    pub vault: AccountInfo<'info>,
+    /// CHECK: This is the global commission vault
+    pub commission_vault: AccountInfo<'info>,
     
-    #[account(mut)]
-    pub creator: Signer<'info>,
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
     
-    pub system_program: Program<'info, System>,
+    /// Mint whose commission is distributed, omitted to distribute SOL commission
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_token_vault", mint.as_ref().map(|mint| mint.key()).unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub commission_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
 #[derive(Accounts)]
-pub struct JoinLobby<'info> {
-    #[account(mut)]
-    pub lobby: Account<'info, Lobby>,
+pub struct ClaimTokenCommission<'info> {
+    #[account(
+        mut,
+        seeds = [b"contract_state"],
+        bump
+    )]
+    pub contract_state: Account<'info, ContractState>,
+    
+    #[account(
+        seeds = [b"commission_vault"],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is the global commission vault, authority of the commission token vaults
+    pub commission_vault: AccountInfo<'info>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         mut,
-        seeds = [b"vault", lobby.key().as_ref()],
+        seeds = [b"commission_token_vault", mint.key().as_ref()],
         bump
     )]
-    /// CHECK: This is just a vault account
-    pub vault: AccountInfo<'info>,
+    pub commission_token_vault: InterfaceAccount<'info, TokenAccount>,
     
-    #[account(mut)]
-    pub opponent: Signer<'info>,
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
     
-    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"multisig"],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&executor.key()) @ GameError::NotMultisigSigner
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    /// Approved WithdrawTokenCommission proposal being executed
+    #[account(
+        mut,
+        seeds = [b"proposal", multisig.key().as_ref(), &proposal.id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    /// Multisig signer executing the withdrawal
+    pub executor: Signer<'info>,
+    
+    /// Receives the commission, must be a token account of global_config.commission_claimer
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = commission_claimer_token_account.owner == global_config.commission_claimer @ GameError::InvalidCommissionClaimer
+    )]
+    pub commission_claimer_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
+
 #[derive(Accounts)]
-pub struct ClaimPrize<'info> {
+pub struct CancelGameTimeout<'info> {
     #[account(mut)]
     pub lobby: Account<'info, Lobby>,
     
@@ -773,9 +4481,9 @@ pub struct ClaimPrize<'info> {
     /// CHECK: This is the global commission vault
     pub commission_vault: AccountInfo<'info>,
     
-    /// The winner who is claiming the prize - must be a signer
+    /// The participant who is cancelling the game
     #[account(mut)]
-    pub winner: Signer<'info>,
+    pub canceller: Signer<'info>,
     
     /// CHECK: Optional referrer account to receive commission
     pub referrer: Option<AccountInfo<'info>>,
@@ -787,43 +4495,33 @@ pub struct ClaimPrize<'info> {
     )]
     pub contract_state: Account<'info, ContractState>,
     
-    /// CHECK: This is the instruction sysvar
-    #[account(address = solana_program::sysvar::instructions::ID)]
-    pub instruction_sysvar: AccountInfo<'info>,
+    /// Stake mint, required for SPL lobbies (validated against lobby.mint)
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
     
-    pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-pub struct ClaimCommission<'info> {
     #[account(
         mut,
-        seeds = [b"contract_state"],
+        seeds = [b"token_vault", lobby.key().as_ref()],
         bump
     )]
-    pub contract_state: Account<'info, ContractState>,
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
     
     #[account(
         mut,
-        seeds = [b"commission_vault"],
-        bump,
-        owner = crate::ID
+        seeds = [b"commission_token_vault", lobby.mint.unwrap_or_default().as_ref()],
+        bump
     )]
-    /// CHECK: This is the global commission vault
-    pub commission_vault: AccountInfo<'info>,
+    pub commission_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
     
-    #[account(
-        mut,
-        constraint = commission_claimer.key() == COMMISSION_CLAIMER
-    )]
-    pub commission_claimer: Signer<'info>,
+    #[account(mut)]
+    pub referrer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     
     pub system_program: Program<'info, System>,
 }
 
-
 #[derive(Accounts)]
-pub struct CancelGameTimeout<'info> {
+pub struct ClaimDrawRefund<'info> {
     #[account(mut)]
     pub lobby: Account<'info, Lobby>,
     
@@ -845,34 +4543,106 @@ pub struct CancelGameTimeout<'info> {
     /// CHECK: This is the global commission vault
     pub commission_vault: AccountInfo<'info>,
     
-    /// CHECK: Creator account to receive refund (validated against lobby.creator)
+    /// The participant claiming their refund - must be a signer
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+    
+    /// CHECK: Optional referrer account to receive commission
+    pub referrer: Option<AccountInfo<'info>>,
+    
+    #[account(
+        mut,
+        seeds = [b"contract_state"],
+        bump
+    )]
+    pub contract_state: Account<'info, ContractState>,
+    
+    #[account(
+        seeds = [b"global_config"],
+        bump = global_config.bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    /// CHECK: This is the instruction sysvar
+    #[account(address = solana_program::sysvar::instructions::ID)]
+    pub instruction_sysvar: AccountInfo<'info>,
+    
+    /// Stake mint, required for SPL lobbies (validated against lobby.mint)
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_vault", lobby.key().as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"commission_token_vault", lobby.mint.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub commission_token_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub claimer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
     #[account(mut)]
-    pub creator: AccountInfo<'info>,
+    pub referrer_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelLobby<'info> {
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator
+    )]
+    pub lobby: Account<'info, Lobby>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", lobby.key().as_ref()],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: This is just a vault account
+    pub vault: AccountInfo<'info>,
     
-    /// CHECK: Opponent account to receive refund (validated against lobby.opponent)
     #[account(mut)]
-    pub opponent: AccountInfo<'info>,
+    pub creator: Signer<'info>,
     
-    /// The participant (creator or opponent) who is cancelling the game
+    /// Stake mint, required for SPL lobbies (validated against lobby.mint)
     #[account(mut)]
-    pub canceller: Signer<'info>,
-    
-    /// CHECK: Optional referrer account to receive commission
-    pub referrer: Option<AccountInfo<'info>>,
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
     
+    /// Token vault of SPL lobbies, closed together with the lobby
     #[account(
         mut,
-        seeds = [b"contract_state"],
+        seeds = [b"token_vault", lobby.key().as_ref()],
         bump
     )]
-    pub contract_state: Account<'info, ContractState>,
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub creator_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimDrawRefund<'info> {
-    #[account(mut)]
+pub struct CloseLobby<'info> {
+    #[account(
+        mut,
+        close = creator,
+        has_one = creator
+    )]
     pub lobby: Account<'info, Lobby>,
     
     #[account(
@@ -885,55 +4655,78 @@ pub struct ClaimDrawRefund<'info> {
     pub vault: AccountInfo<'info>,
     
     #[account(
-        mut,
-        seeds = [b"commission_vault"],
-        bump,
-        owner = crate::ID
+        seeds = [b"spectator_pool", lobby.key().as_ref()],
+        bump
     )]
-    /// CHECK: This is the global commission vault
-    pub commission_vault: AccountInfo<'info>,
+    /// CHECK: Spectator pool PDA of this lobby, which only exists if one was opened
+    pub spectator_pool: AccountInfo<'info>,
     
-    /// The participant claiming their refund - must be a signer
     #[account(mut)]
-    pub claimer: Signer<'info>,
+    pub creator: Signer<'info>,
     
-    /// CHECK: Optional referrer account to receive commission
-    pub referrer: Option<AccountInfo<'info>>,
+    /// Stake mint of SPL lobbies, receives withheld transfer fees before the token vault is closed
+    #[account(mut)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
     
+    /// Token vault of SPL lobbies, closed together with the lobby
     #[account(
         mut,
-        seeds = [b"contract_state"],
+        seeds = [b"token_vault", lobby.key().as_ref()],
         bump
     )]
-    pub contract_state: Account<'info, ContractState>,
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
     
-    /// CHECK: This is the instruction sysvar
-    #[account(address = solana_program::sysvar::instructions::ID)]
-    pub instruction_sysvar: AccountInfo<'info>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CloseLobby<'info> {
-    #[account(
-        mut,
-        close = creator,
-        has_one = creator
-    )]
-    pub lobby: Account<'info, Lobby>,
+pub struct MigrateLobby<'info> {
+    /// CHECK: Pre-versioning lobby, discriminator and size are validated in the handler
+    #[account(mut, owner = crate::ID)]
+    pub lobby: AccountInfo<'info>,
+    
+    /// Pays the extra rent for the larger account
+    #[account(mut)]
+    pub payer: Signer<'info>,
     
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+    /// CHECK: Pre-versioning contract state, discriminator and size are validated in the handler
     #[account(
         mut,
-        seeds = [b"vault", lobby.key().as_ref()],
+        seeds = [b"contract_state"],
         bump,
         owner = crate::ID
     )]
-    /// CHECK: This is just a vault account
-    pub vault: AccountInfo<'info>,
+    pub contract_state: AccountInfo<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GlobalConfig::INIT_SPACE,
+        seeds = [b"global_config"],
+        bump
+    )]
+    pub global_config: Account<'info, GlobalConfig>,
+    
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ GameError::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::SnakeGame>,
+    
+    /// Only the program upgrade authority may migrate the global state
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ GameError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
     
     #[account(mut)]
-    pub creator: Signer<'info>,
+    pub authority: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
@@ -941,7 +4734,414 @@ pub struct CloseLobby<'info> {
 #[account]
 #[derive(InitSpace)]
 pub struct Lobby {
+    pub version: u8,
+    #[max_len(64)]
+    pub id: String,
+    pub creator: Pubkey,
+    pub max_players: u8,
+    #[max_len(MAX_PLAYERS)]
+    pub players: Vec<LobbyPlayer>, // In join order, the creator first
+    #[max_len(MAX_PLAYERS)]
+    pub payout_bps: Vec<u16>, // Share per place, best first; empty means winner takes all
+    pub team_mode: bool, // 2v2, the winning team shares the prize
+    pub winning_team: Option<u8>,
+    pub invite: Option<LobbyInvite>, // None for public lobbies
+    pub series_length: u8, // Best-of-N rounds, 1 for a single game
+    pub rounds_played: u8,
+    pub bonus_amount: u64, // Total sponsored on top of the stakes, always in SOL
+    pub open_bonus_contributions: u16, // Sponsorships not yet paid out or refunded
+    pub bet_amount: u64,
+    pub mint: Option<Pubkey>, // None for SOL lobbies
+    pub status: LobbyStatus,
+    pub winner: Option<Pubkey>,
+    pub forfeited_by: Option<Pubkey>, // Player who conceded, when the game was not played out
+    pub referrer: Option<Pubkey>,
+    pub commission_taken_draw: bool,
+    pub commission_bps: u16,
+    pub referrer_share_bps: u16,
+    pub join_timeout_seconds: i64,
+    pub play_timeout_seconds: i64,
+    pub created_at: i64,
+    pub game_started_at: Option<i64>,
+    pub round_started_at: Option<i64>, // Play timeout runs from here
+    pub completed_at: Option<i64>,
+}
+
+impl Lobby {
+    pub fn player_index(&self, player: &Pubkey) -> Option<usize> {
+        self.players.iter().position(|entry| entry.player == *player)
+    }
+
+    pub fn is_player(&self, player: &Pubkey) -> bool {
+        self.player_index(player).is_some()
+    }
+
+    // Round wins needed to take a best-of-N series
+    pub fn wins_required(&self) -> u8 {
+        self.series_length / 2 + 1
+    }
+
+    // Applies the next series round. A drawn round (no winner) counts as played but awards no win, so it is
+    // effectively replayed and the series runs until someone reaches wins_required. Returns the series
+    // winner once the round decides it.
+    pub fn apply_round(&mut self, round_winner: Option<Pubkey>, now: i64) -> Result<Option<Pubkey>> {
+        let mut series_winner = None;
+        if let Some(winner) = round_winner {
+            let winner_index = self.player_index(&winner).ok_or(GameError::InvalidWinner)?;
+            let winner_entry = &mut self.players[winner_index];
+            winner_entry.wins = winner_entry.wins.checked_add(1).ok_or(GameError::ArithmeticOverflow)?;
+            if winner_entry.wins >= self.wins_required() {
+                series_winner = Some(winner);
+            }
+        }
+        self.rounds_played = self.rounds_played.checked_add(1).ok_or(GameError::ArithmeticOverflow)?;
+        // Play timeout restarts with every round
+        self.round_started_at = Some(now);
+        
+        Ok(series_winner)
+    }
+
+    pub fn team_members(&self, team: u8) -> impl Iterator<Item = &LobbyPlayer> {
+        self.players.iter().filter(move |entry| entry.team == team)
+    }
+
+    // Members of a winning team in join order, each with an equal share of the prize
+    pub fn team_payouts(&self, team: u8) -> (Vec<Pubkey>, Vec<u16>) {
+        let winners = self.team_members(team).map(|entry| entry.player).collect::<Vec<_>>();
+        let shares_bps = vec![BPS_DENOMINATOR / TEAM_SIZE as u16; winners.len()];
+        (winners, shares_bps)
+    }
+
+    // Shares per place, a winner-takes-all lobby pays everything to first place
+    pub fn payout_table(&self) -> Vec<u16> {
+        if self.payout_bps.is_empty() {
+            vec![BPS_DENOMINATOR]
+        } else {
+            self.payout_bps.clone()
+        }
+    }
+
+    pub fn total_escrowed(&self) -> Result<u64> {
+        self.players
+            .iter()
+            .try_fold(0u64, |total, entry| total.checked_add(entry.escrowed))
+            .ok_or(GameError::ArithmeticOverflow.into())
+    }
+
+    // Commission and prizes for a vault holding `total_pool`, which must cover every escrowed stake
+    pub fn settlement(&self, total_pool: u64, shares_bps: &[u16]) -> Result<(u64, Vec<u64>)> {
+        require!(total_pool >= self.total_escrowed()?, GameError::InsufficientVaultBalance);
+        pool_payouts(total_pool, self.commission_bps, shares_bps)
+    }
+
+    // Draw refund for one player: (commission payable on the first claim, this player's refund). Commission
+    // is charged on the stakes actually escrowed and split equally between every player.
+    pub fn draw_refund(&self, player_index: usize) -> Result<(u64, u64)> {
+        let total_commission = calculate_commission(self.total_escrowed()?, self.commission_bps)?;
+        let (payable_commission, commission_per_player) = refund_commission(total_commission, self.players.len())?;
+        let refund_amount = self.players[player_index].escrowed
+            .checked_sub(commission_per_player)
+            .ok_or(GameError::ArithmeticOverflow)?;
+        Ok((payable_commission, refund_amount))
+    }
+
+    // Winner of a played game takes sponsor bonuses. A draw, timeout cancel or forfeit returns them to the
+    // sponsor (None), so two players can't capture a bonus by having one concede.
+    pub fn bonus_winner(&self) -> Result<Option<Pubkey>> {
+        match self.status {
+            LobbyStatus::Completed if self.forfeited_by.is_some() => Ok(None),
+            LobbyStatus::Completed => Ok(Some(self.winner.ok_or(GameError::GameNotFinished)?)),
+            LobbyStatus::Draw | LobbyStatus::Cancelled => Ok(None),
+            _ => Err(GameError::GameNotFinished.into()),
+        }
+    }
+
+    // Every open sponsorship has to be settled before the lobby can close, so a lobby takes a bounded number
+    pub fn add_bonus(&mut self, amount: u64) -> Result<()> {
+        require!(self.open_bonus_contributions < MAX_BONUS_CONTRIBUTIONS, GameError::TooManySponsors);
+        self.bonus_amount = self.bonus_amount.checked_add(amount).ok_or(GameError::ArithmeticOverflow)?;
+        self.open_bonus_contributions += 1;
+        Ok(())
+    }
+
+    // What an invite key signs to let `player` into this lobby
+    pub fn invite_message(&self, player: &Pubkey) -> String {
+        format!("invite:{}:{}", self.id, player)
+    }
+
+    // Creator can back out at any time before anyone else has staked. cancel_lobby closes the lobby, so open
+    // sponsorships must be settled through the timeout path instead, which leaves it for settle_bonus.
+    pub fn check_creator_cancel(&self) -> Result<()> {
+        require!(self.status == LobbyStatus::Waiting, GameError::LobbyNotAvailable);
+        require!(self.players.len() == 1, GameError::PlayersAlreadyJoined);
+        require!(self.open_bonus_contributions == 0, GameError::BonusNotSettled);
+        Ok(())
+    }
+
+    // Moves a waiting lobby into play, either once full or when the creator starts early
+    pub fn start(&mut self, now: i64) {
+        self.status = LobbyStatus::InProgress;
+        self.game_started_at = Some(now);
+        self.round_started_at = Some(now);
+        
+        emit!(GameStarted {
+            lobby_id: self.id.clone(),
+            players: self.players.iter().map(|entry| entry.player).collect(),
+            timestamp: now,
+        });
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug, InitSpace)]
+pub struct LobbyPlayer {
+    pub player: Pubkey,
+    pub team: u8, // Always 0 outside team lobbies
+    pub wins: u8, // Rounds won in a series
+    pub escrowed: u64, // Stake actually received by the vault, net of token transfer fees
+    pub claimed: bool, // Payout or refund already received
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Tournament {
+    #[max_len(64)]
+    pub id: String,
+    pub organizer: Pubkey,
+    pub entry_fee: u64,
+    pub mint: Option<Pubkey>, // None for SOL tournaments
+    pub max_entrants: u8,
+    #[max_len(MAX_TOURNAMENT_ENTRANTS)]
+    pub entrants: Vec<TournamentEntrant>, // In join order, which is also the bracket seeding
+    #[max_len(MAX_TOURNAMENT_ENTRANTS)]
+    pub payout_bps: Vec<u16>, // Share per place, champion first
+    #[max_len(MAX_TOURNAMENT_ENTRANTS / 2)]
+    pub match_winners: Vec<Option<Pubkey>>, // Results of the current round, one per match
+    pub round: u8, // Current round, starting at 1
+    pub status: TournamentStatus,
+    pub referrer: Option<Pubkey>,
+    pub commission_bps: u16,
+    pub referrer_share_bps: u16,
+    pub registration_timeout_seconds: i64,
+    pub match_timeout_seconds: i64,
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub round_started_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl Tournament {
+    pub fn entrant_index(&self, player: &Pubkey) -> Option<usize> {
+        self.entrants.iter().position(|entrant| entrant.player == *player)
+    }
+
+    pub fn total_escrowed(&self) -> Result<u64> {
+        self.entrants
+            .iter()
+            .try_fold(0u64, |total, entrant| total.checked_add(entrant.escrowed))
+            .ok_or(GameError::ArithmeticOverflow.into())
+    }
+
+    // Entrants who started the current round in seeding order; match i pairs entries 2i and 2i + 1
+    pub fn round_players(&self) -> Vec<Pubkey> {
+        self.entrants
+            .iter()
+            .filter(|entrant| !matches!(entrant.eliminated_in, Some(round) if round < self.round))
+            .map(|entrant| entrant.player)
+            .collect()
+    }
+
+    pub fn start_round(&mut self, round: u8, now: i64) {
+        self.round = round;
+        self.round_started_at = Some(now);
+        let players = self.round_players();
+        self.match_winners = vec![None; players.len() / 2];
+        
+        emit!(TournamentRoundStarted {
+            tournament_id: self.id.clone(),
+            round,
+            players,
+            timestamp: now,
+        });
+    }
+
+    // Paid entrants with their share of the prize, champion first. Entrants knocked out in the same round
+    // tie and split the places they cover equally; join order breaks the bps rounding.
+    pub fn placement_shares(&self) -> Vec<(Pubkey, u16)> {
+        let mut standings = self.entrants.iter().collect::<Vec<_>>();
+        standings.sort_by_key(|entrant| std::cmp::Reverse(entrant.eliminated_in.unwrap_or(u8::MAX)));
+        
+        let mut shares = Vec::new();
+        let mut place = 0;
+        while place < self.payout_bps.len() {
+            let eliminated_in = standings[place].eliminated_in;
+            let tier_size = standings[place..].iter().take_while(|entrant| entrant.eliminated_in == eliminated_in).count();
+            let tier_end = place + tier_size;
+            let tier_bps = self.payout_bps[place..tier_end.min(self.payout_bps.len())].iter().sum::<u16>();
+            
+            for (position, entrant) in standings[place..tier_end].iter().enumerate() {
+                let remainder_bps = if position == 0 { tier_bps % tier_size as u16 } else { 0 };
+                let share_bps = tier_bps / tier_size as u16 + remainder_bps;
+                if share_bps > 0 {
+                    shares.push((entrant.player, share_bps));
+                }
+            }
+            place = tier_end;
+        }
+        shares
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug, InitSpace)]
+pub struct TournamentEntrant {
+    pub player: Pubkey,
+    pub escrowed: u64, // Entry fee actually received by the vault, net of token transfer fees
+    pub eliminated_in: Option<u8>, // Round lost, None while still in (or for the champion)
+}
+
+// Sponsor's bonus on a lobby, escrowed as lamports on this account on top of its rent
+#[account]
+#[derive(InitSpace)]
+pub struct BonusContribution {
+    pub lobby: Pubkey,
+    pub sponsor: Pubkey,
+    pub amount: u64,
+    pub commission_bps: u16,
+    pub bump: u8,
+}
+
+// Parimutuel side pool on a lobby, always in SOL
+#[account]
+#[derive(InitSpace)]
+pub struct SpectatorPool {
+    pub lobby: Pubkey,
     #[max_len(64)]
+    pub lobby_id: String,
+    #[max_len(MAX_PLAYERS)]
+    pub backed_totals: Vec<u64>, // Total backing per lobby player, in join order
+    pub commission_bps: u16,
+    pub cutoff_at: i64,
+    pub status: SpectatorPoolStatus,
+    pub winner_index: Option<u8>,
+    pub payout_pool: u64, // Shared by the winner's backers once settled
+    pub opener: Pubkey, // Paid the pool's rent and gets it back on close
+    pub open_bets: u32, // Bets not yet claimed
+    pub settled_at: Option<i64>, // Claim window runs from here
+    pub bump: u8,
+}
+
+impl SpectatorPool {
+    // Follows the lobby result: a winner recorded from a played game pays the backers of that winner. A draw,
+    // timeout cancel, forfeit or a winner nobody backed voids the pool (None) and refunds every bet.
+    pub fn outcome(&self, lobby: &Lobby) -> Result<Option<usize>> {
+        match lobby.status {
+            LobbyStatus::Completed => {
+                // A player could back their opponent from another wallet and then concede
+                if lobby.forfeited_by.is_some() {
+                    return Ok(None);
+                }
+                let winner = lobby.winner.ok_or(GameError::GameNotFinished)?;
+                Ok(lobby.player_index(&winner).filter(|index| self.backed_totals[*index] > 0))
+            },
+            LobbyStatus::Draw | LobbyStatus::Cancelled => Ok(None),
+            _ => Err(GameError::GameNotFinished.into()),
+        }
+    }
+
+    // Winning backers share the pool after commission pro rata (rounded down), losing bets pay nothing,
+    // void pools refund every bet in full
+    pub fn payout(&self, bet: &SpectatorBet) -> Result<u64> {
+        match self.status {
+            SpectatorPoolStatus::Settled => {
+                let winner_index = self.winner_index.ok_or(GameError::SpectatorPoolNotSettled)?;
+                if bet.player_index != winner_index {
+                    return Ok(0);
+                }
+                let winning_total = self.backed_totals[winner_index as usize];
+                let payout = (bet.amount as u128)
+                    .checked_mul(self.payout_pool as u128)
+                    .ok_or(GameError::ArithmeticOverflow)?
+                    .checked_div(winning_total as u128)
+                    .ok_or(GameError::ArithmeticOverflow)?;
+                u64::try_from(payout).map_err(|_| GameError::ArithmeticOverflow.into())
+            },
+            SpectatorPoolStatus::Void => Ok(bet.amount),
+            SpectatorPoolStatus::Open => Err(GameError::SpectatorPoolNotSettled.into()),
+        }
+    }
+
+    // Every bet must be claimed first. Settled pools may also close once winners have had the whole claim
+    // window, but refunds on a void pool stay claimable until the backer collects them.
+    pub fn check_closable(&self, now: i64) -> Result<()> {
+        if self.open_bets == 0 {
+            require!(self.status != SpectatorPoolStatus::Open, GameError::SpectatorPoolNotSettled);
+            return Ok(());
+        }
+        
+        require!(self.status == SpectatorPoolStatus::Settled, GameError::SpectatorClaimsOutstanding);
+        let settled_at = self.settled_at.ok_or(GameError::SpectatorPoolNotSettled)?;
+        let claim_deadline = settled_at.checked_add(SPECTATOR_CLAIM_WINDOW_SECONDS)
+            .ok_or(GameError::ArithmeticOverflow)?;
+        require!(now >= claim_deadline, GameError::SpectatorClaimsOutstanding);
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SpectatorBet {
+    pub pool: Pubkey,
+    pub spectator: Pubkey,
+    pub player_index: u8, // Backed lobby player
+    pub amount: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ContractState {
+    pub version: u8,
+    pub accumulated_commission: u64, // SOL commission, in lamports
+    #[max_len(MAX_STAKE_MINTS)]
+    pub token_commissions: Vec<CommissionLedger>, // One ledger per allowed stake mint, in its base units
+}
+
+impl ContractState {
+    // Ledger for the asset: None is SOL, Some(mint) is the mint's own ledger
+    pub fn commission_ledger(&mut self, mint: Option<Pubkey>) -> Result<&mut u64> {
+        match mint {
+            None => Ok(&mut self.accumulated_commission),
+            Some(mint) => self.token_commissions
+                .iter_mut()
+                .find(|ledger| ledger.mint == mint)
+                .map(|ledger| &mut ledger.accumulated_commission)
+                .ok_or(GameError::MintNotAllowed.into()),
+        }
+    }
+
+    pub fn credit_commission(&mut self, mint: Option<Pubkey>, amount: u64) -> Result<()> {
+        let ledger = self.commission_ledger(mint)?;
+        *ledger = ledger.checked_add(amount).ok_or(GameError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    pub fn debit_commission(&mut self, mint: Option<Pubkey>, amount: u64) -> Result<()> {
+        let ledger = self.commission_ledger(mint)?;
+        require!(*ledger >= amount, GameError::InsufficientCommission);
+        *ledger -= amount;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct CommissionLedger {
+    pub mint: Pubkey,
+    pub accumulated_commission: u64,
+}
+
+// Pre-versioning (v1) layouts, only read by the migrate_* instructions
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct LobbyV1 {
     pub id: String,
     pub creator: Pubkey,
     pub opponent: Option<Pubkey>,
@@ -957,10 +5157,271 @@ pub struct Lobby {
     pub completed_at: Option<i64>,
 }
 
+impl LobbyV1 {
+    // Discriminator + fields, with the id padded to its 64 byte max
+    pub const SPACE: usize = 8 + (4 + 64) + 32 + 33 + 8 + 1 + 33 + 33 + 2 + 2 + 1 + 8 + 9 + 9;
+}
+
+impl From<LobbyV1> for Lobby {
+    fn from(v1: LobbyV1) -> Self {
+        // v1 tracked draw claims per side, while a winner or a timeout cancel paid everyone at once
+        let claimed = |player: Pubkey, claimed_draw: Option<bool>| match v1.status {
+            LobbyStatus::Completed => v1.winner == Some(player),
+            LobbyStatus::Cancelled => true,
+            _ => claimed_draw.unwrap_or(false),
+        };
+        let mut players = vec![LobbyPlayer {
+            player: v1.creator,
+            team: 0,
+            wins: 0,
+            escrowed: v1.bet_amount,
+            claimed: claimed(v1.creator, v1.creator_claimed_draw),
+        }];
+        if let Some(opponent) = v1.opponent {
+            players.push(LobbyPlayer {
+                player: opponent,
+                team: 0,
+                wins: 0,
+                escrowed: v1.bet_amount,
+                claimed: claimed(opponent, v1.opponent_claimed_draw),
+            });
+        }
+        
+        Lobby {
+            version: LOBBY_VERSION,
+            id: v1.id,
+            creator: v1.creator,
+            max_players: MIN_PLAYERS,
+            players,
+            payout_bps: Vec::new(),
+            team_mode: false,
+            winning_team: None,
+            invite: None,
+            series_length: 1,
+            rounds_played: 0,
+            bonus_amount: 0,
+            open_bonus_contributions: 0,
+            bet_amount: v1.bet_amount,
+            mint: None,
+            status: v1.status,
+            winner: v1.winner,
+            forfeited_by: None,
+            referrer: v1.referrer,
+            commission_taken_draw: v1.commission_taken_draw,
+            // v1 lobbies were created under the hardcoded 5% / 50-50 split and 60 minute timeouts
+            commission_bps: V1_COMMISSION_BPS,
+            referrer_share_bps: V1_REFERRER_SHARE_BPS,
+            join_timeout_seconds: GAME_TIMEOUT_SECONDS,
+            play_timeout_seconds: GAME_TIMEOUT_SECONDS,
+            created_at: v1.created_at,
+            game_started_at: v1.game_started_at,
+            round_started_at: v1.game_started_at,
+            completed_at: v1.completed_at,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ContractStateV1 {
+    pub accumulated_commission: u64,
+}
+
+impl ContractStateV1 {
+    pub const SPACE: usize = 8 + 8;
+}
+
+impl From<ContractStateV1> for ContractState {
+    fn from(v1: ContractStateV1) -> Self {
+        ContractState {
+            version: CONTRACT_STATE_VERSION,
+            accumulated_commission: v1.accumulated_commission,
+            token_commissions: Vec::new(),
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct GlobalConfig {
+    pub admin: Pubkey,
+    pub pending_admin: Option<Pubkey>,
+    #[max_len(MAX_ORACLES)]
+    pub oracles: Vec<Pubkey>,
+    pub oracle_threshold: u8,
+    pub commission_claimer: Pubkey,
+    #[max_len(MAX_TREASURY_RECIPIENTS)]
+    pub treasury_recipients: Vec<TreasuryRecipient>, // Weights sum to BPS_DENOMINATOR once configured
+    pub commission_bps: u16,
+    pub referrer_share_bps: u16,
+    pub paused_flags: u8,
+    pub max_bet_amount: u64,
+    #[max_len(MAX_BET_TIERS)]
+    pub bet_tiers: Vec<u64>,
+    pub min_timeout_seconds: i64,
+    pub max_timeout_seconds: i64,
+    pub timelock_delay: i64,
+    pub spectator_commission_bps: u16,
+    pub spectator_cutoff_seconds: i64, // Betting closes this long after the game starts
+    pub bonus_commission_bps: u16,
+    pub config_change_count: u64,
+    pub bump: u8,
+}
+
+impl GlobalConfig {
+    pub fn is_paused(&self, flag: u8) -> bool {
+        self.paused_flags & flag != 0
+    }
+
+    // A lobby or tournament picks its own windows, both within the configured bounds
+    pub fn check_timeouts(&self, join_timeout_seconds: i64, play_timeout_seconds: i64) -> Result<()> {
+        let timeout_range = self.min_timeout_seconds..=self.max_timeout_seconds;
+        require!(timeout_range.contains(&join_timeout_seconds), GameError::InvalidTimeout);
+        require!(timeout_range.contains(&play_timeout_seconds), GameError::InvalidTimeout);
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct TreasuryRecipient {
+    pub recipient: Pubkey,
+    pub weight_bps: u16,
+}
+
+// Allowlist entry for an SPL stake mint, bet limits are in the mint's base units
+#[account]
+#[derive(InitSpace)]
+pub struct StakeMint {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub min_bet_amount: u64,
+    pub max_bet_amount: u64,
+    pub enabled: bool,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Multisig {
+    #[max_len(MAX_MULTISIG_SIGNERS)]
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub proposal_count: u64,
+    pub bump: u8,
+}
+
+impl Multisig {
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+
+    // Only approvals from the current signer set count towards the threshold
+    pub fn approval_count(&self, approvals: &[Pubkey]) -> usize {
+        approvals.iter().filter(|approver| self.is_signer(approver)).count()
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    #[max_len(MAX_MULTISIG_SIGNERS)]
+    pub approvals: Vec<Pubkey>,
+    pub executed: bool,
+    pub created_at: i64,
+    pub eta: Option<i64>,
+    pub bump: u8,
+}
+
+impl Proposal {
+    // A proposal can be cancelled until it executes or its notice period ends
+    pub fn check_cancellable(&self, now: i64) -> Result<()> {
+        require!(!self.executed, GameError::ProposalAlreadyExecuted);
+        if let Some(eta) = self.eta {
+            require!(now < eta, GameError::CancelWindowClosed);
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum ProposalAction {
+    WithdrawCommission { amount: u64 },
+    SetOracles {
+        #[max_len(MAX_ORACLES)]
+        oracles: Vec<Pubkey>,
+        threshold: u8,
+    },
+    SetCommissionClaimer { new_commission_claimer: Pubkey },
+    UpdateMultisig {
+        #[max_len(MAX_MULTISIG_SIGNERS)]
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    },
+    WithdrawTokenCommission { mint: Pubkey, amount: u64 },
+    SetTreasuryRecipients {
+        #[max_len(MAX_TREASURY_RECIPIENTS)]
+        recipients: Vec<TreasuryRecipient>,
+    },
+    TransferAdmin { new_admin: Pubkey },
+    RaiseMaxBet { mint: Option<Pubkey>, max_bet_amount: u64 }, // None is the SOL limit
+    CancelProposal { proposal_id: u64 },
+}
+
+impl ProposalAction {
+    // Everything except commission withdrawal, limit raises and cancellations changes configuration and
+    // needs a notice period
+    pub fn is_timelocked(&self) -> bool {
+        !matches!(
+            self,
+            ProposalAction::WithdrawCommission { .. }
+                | ProposalAction::WithdrawTokenCommission { .. }
+                | ProposalAction::RaiseMaxBet { .. }
+                | ProposalAction::CancelProposal { .. }
+        )
+    }
+}
+
 #[account]
 #[derive(InitSpace)]
-pub struct ContractState {
-    pub accumulated_commission: u64,
+pub struct QueuedConfigChange {
+    pub id: u64,
+    pub change: ConfigChange,
+    pub queued_by: Pubkey,
+    pub queued_at: i64,
+    pub eta: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum ConfigChange {
+    CommissionRates { commission_bps: u16, referrer_share_bps: u16 },
+    TimeoutBounds { min_timeout_seconds: i64, max_timeout_seconds: i64 },
+    TimelockDelay { delay_seconds: i64 },
+    SpectatorTerms { commission_bps: u16, cutoff_seconds: i64 },
+    BonusCommission { commission_bps: u16 },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, InitSpace)]
+pub enum LobbyInvite {
+    Opponent(Pubkey),
+    InviteKey(Pubkey), // Keypair shared off-chain, joiners bring its signature over their own address
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum SpectatorPoolStatus {
+    Open,
+    Settled,
+    Void, // Every bet is refunded
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum TournamentStatus {
+    Registration,
+    InProgress,
+    Completed,
+    Cancelled,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
@@ -1036,6 +5497,152 @@ pub enum GameError {
     LobbyIdTooLong,
     #[msg("Lobby ID contains invalid characters, only alphanumeric, underscore, and dash allowed")]
     InvalidLobbyId,
+    #[msg("Signer is not authorized to perform this action")]
+    Unauthorized,
+    #[msg("Invalid authority pubkey")]
+    InvalidAuthority,
+    #[msg("Invalid commission claimer")]
+    InvalidCommissionClaimer,
+    #[msg("Commission rate exceeds the allowed maximum")]
+    CommissionRateTooHigh,
+    #[msg("Referrer share must not exceed 100%")]
+    InvalidReferrerShare,
+    #[msg("This instruction is currently paused")]
+    InstructionPaused,
+    #[msg("Unknown pause flags provided")]
+    InvalidPauseFlags,
+    #[msg("Signer is not the pending admin")]
+    NotPendingAdmin,
+    #[msg("Invalid multisig signers or threshold")]
+    InvalidMultisigConfig,
+    #[msg("Signer is not a member of the multisig")]
+    NotMultisigSigner,
+    #[msg("Proposal already approved by this signer")]
+    ProposalAlreadyApproved,
+    #[msg("Proposal already executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Proposal does not have enough approvals")]
+    ProposalThresholdNotMet,
+    #[msg("Proposal action cannot be executed by this instruction")]
+    InvalidProposalAction,
+    #[msg("Timelock delay has not passed yet")]
+    TimelockNotExpired,
+    #[msg("Timelock delay is outside the allowed range")]
+    InvalidTimelockDelay,
+    #[msg("Timeout is outside the configured min and max")]
+    InvalidTimeout,
+    #[msg("Invalid timeout bounds")]
+    InvalidTimeoutBounds,
+    #[msg("Bet amount exceeds the maximum allowed")]
+    BetAmountTooLarge,
+    #[msg("Bet amount does not match any allowed bet tier")]
+    BetAmountNotInTiers,
+    #[msg("Invalid max bet or bet tiers")]
+    InvalidBetLimits,
+    #[msg("Raising a max bet needs an approved multisig proposal")]
+    BetLimitRaiseNotApproved,
+    #[msg("Invalid oracle set or threshold")]
+    InvalidOracleSet,
+    #[msg("Signature is not from a registered oracle")]
+    UnknownOracle,
+    #[msg("Oracle signed more than once")]
+    DuplicateOracleSignature,
+    #[msg("Not enough oracle signatures")]
+    OracleThresholdNotMet,
+    #[msg("Account is already on the current version")]
+    AccountAlreadyMigrated,
+    #[msg("Account data does not match a known version")]
+    InvalidAccountVersion,
+    #[msg("Token accounts are required for SPL lobbies")]
+    MissingTokenAccounts,
+    #[msg("Token mint does not match the lobby")]
+    InvalidMint,
+    #[msg("Token account is not owned by the expected wallet")]
+    InvalidTokenAccountOwner,
+    #[msg("Mint has a token extension that is not supported")]
+    UnsupportedMintExtension,
+    #[msg("Mint is not in the stake mint allowlist")]
+    MintNotAllowed,
+    #[msg("Stake mint allowlist is full")]
+    TooManyStakeMints,
+    #[msg("Treasury recipients must be unique, non-zero and weights must sum to 100%")]
+    InvalidTreasuryRecipients,
+    #[msg("Treasury recipients are not configured")]
+    TreasuryRecipientsNotSet,
+    #[msg("Max players must be between 2 and 8")]
+    InvalidMaxPlayers,
+    #[msg("Player has already joined this lobby")]
+    AlreadyJoined,
+    #[msg("Not enough players to start the game")]
+    NotEnoughPlayers,
+    #[msg("Payout account does not match the player")]
+    InvalidPayoutAccount,
+    #[msg("Payout table must be non-increasing, fit the lobby and sum to 100%")]
+    InvalidPayoutTable,
+    #[msg("Ranking must list one distinct player per paid place")]
+    InvalidRanking,
+    #[msg("Lobby has a payout table and must be settled by ranking")]
+    RankedSettlementRequired,
+    #[msg("Team lobby must be settled by winning team")]
+    TeamSettlementRequired,
+    #[msg("Lobby is not a team lobby")]
+    NotTeamLobby,
+    #[msg("Invalid team selection")]
+    InvalidTeam,
+    #[msg("Team is full")]
+    TeamFull,
+    #[msg("Team lobbies start once both teams are full")]
+    InvalidTeamSize,
+    #[msg("Invalid lobby invite")]
+    InvalidInvite,
+    #[msg("Lobby is private and the signer is not the invited opponent")]
+    NotInvited,
+    #[msg("Lobby is private and the join is not signed by the invite key")]
+    InvalidInviteSignature,
+    #[msg("Series length must be odd and within limits, and series are 1v1 only")]
+    InvalidSeriesLength,
+    #[msg("Series lobby must be settled round by round")]
+    SeriesSettlementRequired,
+    #[msg("Lobby is not a series lobby")]
+    NotSeriesLobby,
+    #[msg("Round is not the next round of the series")]
+    InvalidRound,
+    #[msg("Tournament size must be a power of two within limits")]
+    InvalidTournamentSize,
+    #[msg("Tournament is not open for registration")]
+    TournamentNotOpen,
+    #[msg("Tournament is full")]
+    TournamentFull,
+    #[msg("Tournament is not in progress")]
+    TournamentNotInProgress,
+    #[msg("Match is not part of the current round")]
+    InvalidMatch,
+    #[msg("Match result already recorded")]
+    MatchAlreadyRecorded,
+    #[msg("Lobby does not support spectator pools")]
+    SpectatorPoolUnsupported,
+    #[msg("Spectator pool is no longer open")]
+    SpectatorPoolClosed,
+    #[msg("Spectator pool has not been settled yet")]
+    SpectatorPoolNotSettled,
+    #[msg("Spectator bets are still unclaimed and the claim window has not passed")]
+    SpectatorClaimsOutstanding,
+    #[msg("Betting cutoff has passed")]
+    BettingClosed,
+    #[msg("Players cannot bet on their own game")]
+    PlayersCannotSpectate,
+    #[msg("Lobby does not support bonus sponsorship")]
+    BonusUnsupported,
+    #[msg("Lobby still has unsettled bonus contributions")]
+    BonusNotSettled,
+    #[msg("Other players already joined the lobby")]
+    PlayersAlreadyJoined,
+    #[msg("Only 1v1 lobbies can be forfeited")]
+    ForfeitUnsupported,
+    #[msg("Notice period has ended, proposal can no longer be cancelled")]
+    CancelWindowClosed,
+    #[msg("Lobby already has the maximum number of sponsors")]
+    TooManySponsors,
 }
 
 // Events
@@ -1044,13 +5651,178 @@ pub struct LobbyCreated {
     pub lobby_id: String,
     pub creator: Pubkey,
     pub bet_amount: u64,
+    pub escrowed_amount: u64,
+    pub max_players: u8,
+    pub payout_bps: Vec<u16>,
+    pub team_mode: bool,
+    pub invite: Option<LobbyInvite>,
+    pub series_length: u8,
+    pub mint: Option<Pubkey>,
+    pub join_timeout_seconds: i64,
+    pub play_timeout_seconds: i64,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct PlayerJoined {
     pub lobby_id: String,
-    pub opponent: Pubkey,
+    pub player: Pubkey,
+    pub escrowed_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameStarted {
+    pub lobby_id: String,
+    pub players: Vec<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BonusSponsored {
+    pub lobby_id: String,
+    pub sponsor: Pubkey,
+    pub amount: u64,
+    pub total_bonus: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BonusSettled {
+    pub lobby_id: String,
+    pub sponsor: Pubkey,
+    pub recipient: Pubkey, // Winner, or the sponsor when refunded
+    pub amount: u64,
+    pub commission: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SpectatorPoolOpened {
+    pub lobby_id: String,
+    pub commission_bps: u16,
+    pub cutoff_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SpectatorBetPlaced {
+    pub lobby_id: String,
+    pub spectator: Pubkey,
+    pub backed_player: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SpectatorPoolSettled {
+    pub lobby_id: String,
+    pub winner: Option<Pubkey>, // None when the pool was voided
+    pub total_pool: u64,
+    pub commission: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SpectatorPayoutClaimed {
+    pub lobby_id: String,
+    pub spectator: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SpectatorPoolSwept {
+    pub lobby_id: String,
+    pub unclaimed_bets: u32,
+    pub swept_amount: u64, // Rounding dust plus anything left unclaimed, credited as commission
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentCreated {
+    pub tournament_id: String,
+    pub organizer: Pubkey,
+    pub entry_fee: u64,
+    pub mint: Option<Pubkey>,
+    pub max_entrants: u8,
+    pub payout_bps: Vec<u16>,
+    pub referrer: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentJoined {
+    pub tournament_id: String,
+    pub player: Pubkey,
+    pub escrowed_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentRoundStarted {
+    pub tournament_id: String,
+    pub round: u8,
+    pub players: Vec<Pubkey>, // Bracket order, match i pairs entries 2i and 2i + 1
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentMatchRecorded {
+    pub tournament_id: String,
+    pub round: u8,
+    pub match_index: u8,
+    pub winner: Pubkey,
+    pub loser: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentCompleted {
+    pub tournament_id: String,
+    pub standings: Vec<Pubkey>, // Paid entrants, champion first
+    pub prizes: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TournamentCancelled {
+    pub tournament_id: String,
+    pub canceller: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoundRecorded {
+    pub lobby_id: String,
+    pub round: u8,
+    pub winner: Option<Pubkey>, // None for a drawn round
+    pub wins: Vec<u8>, // Score per player, in join order
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TeamJoined {
+    pub lobby_id: String,
+    pub player: Pubkey,
+    pub team: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TeamGameCompleted {
+    pub lobby_id: String,
+    pub winning_team: u8,
+    pub winners: Vec<Pubkey>,
+    pub prizes: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RankedGameCompleted {
+    pub lobby_id: String,
+    pub ranking: Vec<Pubkey>,
+    pub prizes: Vec<u64>,
     pub timestamp: i64,
 }
 
@@ -1066,6 +5838,7 @@ pub struct GameCompleted {
 #[event]
 pub struct CommissionClaimed {
     pub claimer: Pubkey,
+    pub mint: Option<Pubkey>,
     pub amount: u64,
     pub timestamp: i64,
 }
@@ -1077,6 +5850,22 @@ pub struct GameTimeoutCancelled {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct GameForfeited {
+    pub lobby_id: String,
+    pub forfeited_by: Pubkey,
+    pub winner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LobbyCancelled {
+    pub lobby_id: String,
+    pub creator: Pubkey,
+    pub refund_amount: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct GameDeclaredDraw {
     pub lobby_id: String,
@@ -1091,18 +5880,291 @@ pub struct DrawRefundClaimed {
     pub timestamp: i64,
 }
 
-// Backend authority pubkey (replace with your backend's keypair pubkey)
-pub const BACKEND_AUTHORITY: Pubkey = solana_program::pubkey!("FrmyQzmFNBeEiUUA1nkv4Yh9KDB8fheeaCQqQZZCp53S");
+#[event]
+pub struct OracleSetUpdated {
+    pub oracles: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryRecipientsUpdated {
+    pub recipients: Vec<TreasuryRecipient>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CommissionDistributed {
+    pub recipient: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub weight_bps: u16,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CommissionClaimerUpdated {
+    pub old_commission_claimer: Pubkey,
+    pub new_commission_claimer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferProposed {
+    pub current_admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminUpdated {
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MultisigUpdated {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalApproved {
+    pub proposal_id: u64,
+    pub approver: Pubkey,
+    pub approvals: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalQueued {
+    pub proposal_id: u64,
+    pub action: ProposalAction,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCancelled {
+    pub proposal_id: u64,
+    pub cancellation_id: u64,
+    pub executor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub proposal_id: u64,
+    pub executor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeQueued {
+    pub change_id: u64,
+    pub change: ConfigChange,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeExecuted {
+    pub change_id: u64,
+    pub change: ConfigChange,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeCancelled {
+    pub change_id: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LobbyMigrated {
+    pub lobby_id: String,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StateMigrated {
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CommissionRatesUpdated {
+    pub commission_bps: u16,
+    pub referrer_share_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TimeoutBoundsUpdated {
+    pub min_timeout_seconds: i64,
+    pub max_timeout_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TimelockDelayUpdated {
+    pub delay_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SpectatorTermsUpdated {
+    pub commission_bps: u16,
+    pub cutoff_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BonusCommissionUpdated {
+    pub commission_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetLimitsUpdated {
+    pub max_bet_amount: u64,
+    pub bet_tiers: Vec<u64>,
+    pub timestamp: i64,
+}
 
-// Commission claimer pubkey
-pub const COMMISSION_CLAIMER: Pubkey = solana_program::pubkey!("3wSMiq3LLjawSCnMpcSrAF7a5D9CazWyLotEaEP4Mkch");
+#[event]
+pub struct StakeMintUpdated {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub min_bet_amount: u64,
+    pub max_bet_amount: u64,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseFlagsUpdated {
+    pub paused_flags: u8,
+    pub timestamp: i64,
+}
 
 // Minimum bet amount (0.01 SOL = 10_000_000 lamports)
 pub const MIN_BET_AMOUNT: u64 = 10_000_000;
 
-// Timeout period for game cancellation (60 minutes in seconds)
+// Default maximum bet amount (10 SOL = 10_000_000_000 lamports)
+pub const DEFAULT_MAX_BET_AMOUNT: u64 = 10_000_000_000;
+
+// Maximum number of allowed bet tiers in GlobalConfig
+pub const MAX_BET_TIERS: usize = 10;
+
+// Players per lobby, the creator included
+pub const MIN_PLAYERS: u8 = 2;
+pub const MAX_PLAYERS: u8 = 8;
+
+// Entrants per tournament bracket
+pub const MIN_TOURNAMENT_ENTRANTS: u8 = 4;
+pub const MAX_TOURNAMENT_ENTRANTS: u8 = 16;
+
+// Longest best-of-N series
+pub const MAX_SERIES_LENGTH: u8 = 9;
+
+// Team lobbies are 2v2
+pub const TEAM_COUNT: u8 = 2;
+pub const TEAM_SIZE: u8 = 2;
+
+// Maximum number of treasury recipients sharing distributed commission
+pub const MAX_TREASURY_RECIPIENTS: usize = 8;
+
+// Maximum number of allowed SPL stake mints, each with its own commission ledger
+pub const MAX_STAKE_MINTS: usize = 16;
+
+// Token-2022 mint extensions that leave vault balances under program control
+const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 9] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::GroupPointer,
+    ExtensionType::TokenGroup,
+    ExtensionType::GroupMemberPointer,
+    ExtensionType::TokenGroupMember,
+];
+
+// Current account layout versions (v1 is the original layout without a version byte)
+pub const LOBBY_VERSION: u8 = 2;
+pub const CONTRACT_STATE_VERSION: u8 = 2;
+
+// Commission terms every v1 lobby was created under (5% with a 50/50 referrer split)
+pub const V1_COMMISSION_BPS: u16 = 500;
+pub const V1_REFERRER_SHARE_BPS: u16 = 5_000;
+
+// Commission rates are expressed in basis points (10_000 = 100%)
+pub const BPS_DENOMINATOR: u16 = 10_000;
+
+// Default platform commission (5% of the total pool)
+pub const DEFAULT_COMMISSION_BPS: u16 = 500;
+
+// Default referrer share of the commission (50%, i.e. 2.5% of the pool)
+pub const DEFAULT_REFERRER_SHARE_BPS: u16 = 5_000;
+
+// Upper bound for the platform commission (10% of the total pool)
+pub const MAX_COMMISSION_BPS: u16 = 1_000;
+
+// Notice period before queued config changes and authority rotations can apply
+pub const DEFAULT_TIMELOCK_DELAY_SECONDS: i64 = 48 * 60 * 60;
+pub const MIN_TIMELOCK_DELAY_SECONDS: i64 = 24 * 60 * 60;
+pub const MAX_TIMELOCK_DELAY_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+// Default spectator pool terms: 5% commission, betting open for the first 2 minutes of a game
+pub const DEFAULT_SPECTATOR_COMMISSION_BPS: u16 = 500;
+pub const DEFAULT_SPECTATOR_CUTOFF_SECONDS: i64 = 2 * 60;
+
+// Winning backers have 30 days after settlement to claim before a settled pool can be closed with bets outstanding
+pub const SPECTATOR_CLAIM_WINDOW_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+// Sponsored bonuses carry no commission by default
+pub const DEFAULT_BONUS_COMMISSION_BPS: u16 = 0;
+
+// Maximum number of open sponsorships per lobby
+pub const MAX_BONUS_CONTRIBUTIONS: u16 = 64;
+
+// Maximum number of registered backend oracles
+pub const MAX_ORACLES: usize = 10;
+
+// Maximum number of signers in the admin multisig
+pub const MAX_MULTISIG_SIGNERS: usize = 10;
+
+// Pause flags stored in GlobalConfig.paused_flags (refund paths cannot be paused)
+pub const PAUSE_CREATE_LOBBY: u8 = 1 << 0;
+pub const PAUSE_JOIN_LOBBY: u8 = 1 << 1;
+pub const PAUSE_CLAIM_PRIZE: u8 = 1 << 2;
+pub const PAUSE_CLAIM_COMMISSION: u8 = 1 << 3;
+pub const PAUSE_ALL: u8 = PAUSE_CREATE_LOBBY | PAUSE_JOIN_LOBBY | PAUSE_CLAIM_PRIZE | PAUSE_CLAIM_COMMISSION;
+
+// Timeout period for game cancellation (60 minutes in seconds), now the default upper bound for lobbies
 pub const GAME_TIMEOUT_SECONDS: i64 = 60 * 60;
 
+// Default bounds for per-lobby join and play timeouts (1 minute to 60 minutes)
+pub const DEFAULT_MIN_TIMEOUT_SECONDS: i64 = 60;
+pub const DEFAULT_MAX_TIMEOUT_SECONDS: i64 = GAME_TIMEOUT_SECONDS;
+
+// Hard upper bound the admin can raise the max timeout to (7 days)
+pub const MAX_TIMEOUT_LIMIT_SECONDS: i64 = 7 * 24 * 60 * 60;
+
 // Ed25519 signature verification constants
 pub const PUBKEY_SERIALIZED_SIZE: usize = 32;
 pub const SIGNATURE_SERIALIZED_SIZE: usize = 64;
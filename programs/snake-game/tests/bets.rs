@@ -0,0 +1,82 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::global_config;
+use snake_game::{
+    validate_bet_limits, validate_stake_amount, GameError, GlobalConfig, StakeMint, MAX_BET_TIERS, MIN_BET_AMOUNT,
+};
+
+const SOL: u64 = 1_000_000_000;
+
+#[test]
+fn bet_tiers_must_increase_within_the_sol_limits() {
+    validate_bet_limits(SOL, &[]).unwrap();
+    validate_bet_limits(SOL, &[MIN_BET_AMOUNT, SOL / 2, SOL]).unwrap();
+
+    let too_many: Vec<u64> = (0..=MAX_BET_TIERS as u64).map(|tier| MIN_BET_AMOUNT + tier).collect();
+    for (max_bet_amount, bet_tiers) in [
+        (MIN_BET_AMOUNT - 1, vec![]),
+        (SOL, vec![SOL / 2, SOL / 2]),
+        (SOL, vec![SOL, SOL / 2]),
+        (SOL, vec![MIN_BET_AMOUNT - 1]),
+        (SOL, vec![SOL + 1]),
+        (SOL, too_many),
+    ] {
+        let err = validate_bet_limits(max_bet_amount, &bet_tiers).unwrap_err();
+        assert_eq!(err, GameError::InvalidBetLimits.into());
+    }
+}
+
+#[test]
+fn sol_stake_must_sit_within_the_max_bet() {
+    let config = GlobalConfig { max_bet_amount: SOL, ..global_config() };
+
+    validate_stake_amount(MIN_BET_AMOUNT, false, None, &config).unwrap();
+    validate_stake_amount(SOL, false, None, &config).unwrap();
+
+    let err = validate_stake_amount(MIN_BET_AMOUNT - 1, false, None, &config).unwrap_err();
+    assert_eq!(err, GameError::BetAmountTooSmall.into());
+    let err = validate_stake_amount(SOL + 1, false, None, &config).unwrap_err();
+    assert_eq!(err, GameError::BetAmountTooLarge.into());
+}
+
+#[test]
+fn sol_stake_must_match_a_tier_once_tiers_are_set() {
+    let config = GlobalConfig { max_bet_amount: SOL, bet_tiers: vec![SOL / 10, SOL / 2], ..global_config() };
+
+    validate_stake_amount(SOL / 10, false, None, &config).unwrap();
+    validate_stake_amount(SOL / 2, false, None, &config).unwrap();
+
+    // Within the limits but between tiers
+    let err = validate_stake_amount(SOL / 4, false, None, &config).unwrap_err();
+    assert_eq!(err, GameError::BetAmountNotInTiers.into());
+}
+
+#[test]
+fn token_stake_needs_an_enabled_mint_and_uses_its_own_limits() {
+    // SOL limits and tiers don't apply to token stakes
+    let config = GlobalConfig { max_bet_amount: SOL, bet_tiers: vec![SOL], ..global_config() };
+    let usdc = StakeMint {
+        mint: Pubkey::new_unique(),
+        decimals: 6,
+        min_bet_amount: 1_000_000,
+        max_bet_amount: 5_000_000_000,
+        enabled: true,
+        bump: 255,
+    };
+
+    validate_stake_amount(1_000_000, true, Some(&usdc), &config).unwrap();
+    validate_stake_amount(5_000_000_000, true, Some(&usdc), &config).unwrap();
+
+    let err = validate_stake_amount(999_999, true, Some(&usdc), &config).unwrap_err();
+    assert_eq!(err, GameError::BetAmountTooSmall.into());
+    let err = validate_stake_amount(5_000_000_001, true, Some(&usdc), &config).unwrap_err();
+    assert_eq!(err, GameError::BetAmountTooLarge.into());
+
+    // A mint that isn't allowlisted, or was disabled, can't be staked at all
+    let err = validate_stake_amount(1_000_000, true, None, &config).unwrap_err();
+    assert_eq!(err, GameError::MintNotAllowed.into());
+    let disabled = StakeMint { enabled: false, ..usdc };
+    let err = validate_stake_amount(1_000_000, true, Some(&disabled), &config).unwrap_err();
+    assert_eq!(err, GameError::MintNotAllowed.into());
+}
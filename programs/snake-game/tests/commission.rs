@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use snake_game::{
+    treasury_shares, validate_treasury_recipients, GameError, TreasuryRecipient, MAX_TREASURY_RECIPIENTS,
+};
+
+fn recipients(weights_bps: &[u16]) -> Vec<TreasuryRecipient> {
+    weights_bps
+        .iter()
+        .map(|weight_bps| TreasuryRecipient { recipient: Pubkey::new_unique(), weight_bps: *weight_bps })
+        .collect()
+}
+
+#[test]
+fn treasury_shares_follow_the_recipient_weights() {
+    // Ops, marketing and dev fund
+    let shares = treasury_shares(1_000_000_000, &recipients(&[5_000, 3_000, 2_000])).unwrap();
+
+    assert_eq!(shares, vec![500_000_000, 300_000_000, 200_000_000]);
+}
+
+#[test]
+fn treasury_rounding_dust_stays_in_the_ledger() {
+    for distributable in [0, 1, 2, 10, 9_999, 1_000_003, u64::MAX] {
+        let shares = treasury_shares(distributable, &recipients(&[3_334, 3_333, 3_333])).unwrap();
+        let distributed: u128 = shares.iter().map(|share| *share as u128).sum();
+
+        // Never more than the ledger, and at most a unit per recipient left behind
+        assert!(distributed <= distributable as u128);
+        assert!(distributable as u128 - distributed < shares.len() as u128);
+    }
+}
+
+#[test]
+fn treasury_weights_must_split_the_whole_balance() {
+    validate_treasury_recipients(&recipients(&[10_000])).unwrap();
+    validate_treasury_recipients(&recipients(&[5_000, 3_000, 2_000])).unwrap();
+
+    for weights_bps in [vec![], vec![5_000, 3_000], vec![5_000, 3_000, 3_000], vec![10_000, 0]] {
+        let err = validate_treasury_recipients(&recipients(&weights_bps)).unwrap_err();
+        assert_eq!(err, GameError::InvalidTreasuryRecipients.into());
+    }
+
+    let too_many = vec![1_000; MAX_TREASURY_RECIPIENTS + 1];
+    let err = validate_treasury_recipients(&recipients(&too_many)).unwrap_err();
+    assert_eq!(err, GameError::InvalidTreasuryRecipients.into());
+}
+
+#[test]
+fn treasury_recipients_must_be_distinct_real_accounts() {
+    let mut duplicated = recipients(&[5_000, 5_000]);
+    duplicated[1].recipient = duplicated[0].recipient;
+    let err = validate_treasury_recipients(&duplicated).unwrap_err();
+    assert_eq!(err, GameError::InvalidTreasuryRecipients.into());
+
+    let mut unset = recipients(&[5_000, 5_000]);
+    unset[0].recipient = Pubkey::default();
+    let err = validate_treasury_recipients(&unset).unwrap_err();
+    assert_eq!(err, GameError::InvalidTreasuryRecipients.into());
+}
@@ -0,0 +1,116 @@
+// Shared by every test binary, which each use only part of it
+#![allow(dead_code)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use snake_game::{
+    GlobalConfig, Lobby, LobbyPlayer, LobbyStatus, DEFAULT_BONUS_COMMISSION_BPS, DEFAULT_COMMISSION_BPS,
+    DEFAULT_MAX_BET_AMOUNT, DEFAULT_MAX_TIMEOUT_SECONDS, DEFAULT_MIN_TIMEOUT_SECONDS, DEFAULT_REFERRER_SHARE_BPS,
+    DEFAULT_SPECTATOR_COMMISSION_BPS, DEFAULT_SPECTATOR_CUTOFF_SECONDS, DEFAULT_TIMELOCK_DELAY_SECONDS,
+    GAME_TIMEOUT_SECONDS, LOBBY_VERSION,
+};
+
+// Config as initialize leaves it, with a single oracle
+pub fn global_config() -> GlobalConfig {
+    GlobalConfig {
+        admin: Pubkey::new_unique(),
+        pending_admin: None,
+        oracles: vec![Pubkey::new_unique()],
+        oracle_threshold: 1,
+        commission_claimer: Pubkey::new_unique(),
+        treasury_recipients: Vec::new(),
+        commission_bps: DEFAULT_COMMISSION_BPS,
+        referrer_share_bps: DEFAULT_REFERRER_SHARE_BPS,
+        paused_flags: 0,
+        max_bet_amount: DEFAULT_MAX_BET_AMOUNT,
+        bet_tiers: Vec::new(),
+        min_timeout_seconds: DEFAULT_MIN_TIMEOUT_SECONDS,
+        max_timeout_seconds: DEFAULT_MAX_TIMEOUT_SECONDS,
+        timelock_delay: DEFAULT_TIMELOCK_DELAY_SECONDS,
+        spectator_commission_bps: DEFAULT_SPECTATOR_COMMISSION_BPS,
+        spectator_cutoff_seconds: DEFAULT_SPECTATOR_CUTOFF_SECONDS,
+        bonus_commission_bps: DEFAULT_BONUS_COMMISSION_BPS,
+        config_change_count: 0,
+        bump: 255,
+    }
+}
+
+// Winner-takes-all SOL lobby in progress with the given players at the default terms. Tests shape it for
+// other formats and states with struct update syntax.
+pub fn lobby(players: Vec<LobbyPlayer>) -> Lobby {
+    Lobby {
+        version: LOBBY_VERSION,
+        id: "lobby-1".to_string(),
+        creator: players[0].player,
+        max_players: players.len() as u8,
+        players,
+        payout_bps: Vec::new(),
+        team_mode: false,
+        winning_team: None,
+        invite: None,
+        series_length: 1,
+        rounds_played: 0,
+        bonus_amount: 0,
+        open_bonus_contributions: 0,
+        bet_amount: 100_000_000,
+        mint: None,
+        status: LobbyStatus::InProgress,
+        winner: None,
+        forfeited_by: None,
+        referrer: None,
+        commission_taken_draw: false,
+        commission_bps: DEFAULT_COMMISSION_BPS,
+        referrer_share_bps: DEFAULT_REFERRER_SHARE_BPS,
+        join_timeout_seconds: GAME_TIMEOUT_SECONDS,
+        play_timeout_seconds: GAME_TIMEOUT_SECONDS,
+        created_at: 1_700_000_000,
+        game_started_at: Some(1_700_000_100),
+        round_started_at: Some(1_700_000_100),
+        completed_at: None,
+    }
+}
+
+pub fn player(team: u8) -> LobbyPlayer {
+    LobbyPlayer { player: Pubkey::new_unique(), team, wins: 0, escrowed: 100_000_000, claimed: false }
+}
+
+// Completes a lobby the way forfeit (when `forfeited_by` is set) or record_round and claim_prize leave it
+pub fn won_by(mut lobby: Lobby, winner: usize, forfeited_by: Option<usize>) -> Lobby {
+    lobby.status = LobbyStatus::Completed;
+    lobby.winner = Some(lobby.players[winner].player);
+    lobby.forfeited_by = forfeited_by.map(|index| lobby.players[index].player);
+    lobby.completed_at = Some(1_700_000_200);
+    lobby
+}
+
+// Serializes writable accounts, given as (owner, data), the way the runtime hands them to the entrypoint, so
+// AccountInfos read back from it have the realloc headroom resize and close rely on
+pub fn runtime_input(accounts: &[(Pubkey, &[u8])]) -> Vec<u64> {
+    let mut input = Vec::new();
+    input.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+    for (owner, data) in accounts {
+        input.push(u8::MAX); // not a duplicate
+        input.extend_from_slice(&[0, 1, 0]); // is_signer, is_writable, executable
+        input.extend_from_slice(&[0; 4]); // original data length, filled in by deserialize
+        input.extend_from_slice(Pubkey::new_unique().as_ref());
+        input.extend_from_slice(owner.as_ref());
+        input.extend_from_slice(&1_000_000u64.to_le_bytes());
+        input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        input.extend_from_slice(data);
+        input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        input.resize(input.len().next_multiple_of(8), 0);
+        input.extend_from_slice(&0u64.to_le_bytes()); // rent epoch
+    }
+    input.extend_from_slice(&0u64.to_le_bytes()); // no instruction data
+    input.extend_from_slice(snake_game::ID.as_ref());
+
+    // Backed by u64s so the buffer has the alignment the runtime guarantees
+    input
+        .chunks(8)
+        .map(|chunk| {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        })
+        .collect()
+}
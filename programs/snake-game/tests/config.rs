@@ -0,0 +1,94 @@
+mod common;
+
+use common::global_config;
+use snake_game::{
+    validate_config_change, validate_pause_flags, ConfigChange, GameError, GlobalConfig, MAX_TIMEOUT_LIMIT_SECONDS,
+    PAUSE_ALL, PAUSE_CLAIM_COMMISSION, PAUSE_CLAIM_PRIZE, PAUSE_CREATE_LOBBY, PAUSE_JOIN_LOBBY,
+};
+
+const PAUSE_BITS: [u8; 4] = [PAUSE_CREATE_LOBBY, PAUSE_JOIN_LOBBY, PAUSE_CLAIM_PRIZE, PAUSE_CLAIM_COMMISSION];
+
+// Handler body of a `pub fn` in the program module, up to the next one
+fn handler_source(name: &str) -> &'static str {
+    let source = include_str!("../src/lib.rs");
+    let start = source.find(&format!("    pub fn {name}")).unwrap();
+    let end = source[start + 1..].find("\n    pub fn ").map_or(source.len(), |offset| start + 1 + offset);
+    &source[start..end]
+}
+
+#[test]
+fn pause_bits_stop_only_their_own_instructions() {
+    for bit in PAUSE_BITS {
+        let config = GlobalConfig { paused_flags: bit, ..global_config() };
+        for other in PAUSE_BITS {
+            assert_eq!(config.is_paused(other), other == bit);
+        }
+    }
+
+    let config = GlobalConfig { paused_flags: PAUSE_ALL, ..global_config() };
+    assert!(PAUSE_BITS.iter().all(|bit| config.is_paused(*bit)));
+}
+
+#[test]
+fn only_defined_pause_bits_can_be_set() {
+    validate_pause_flags(0).unwrap();
+    validate_pause_flags(PAUSE_ALL).unwrap();
+
+    for undefined in (0..8).map(|shift| 1u8 << shift).filter(|bit| PAUSE_ALL & bit == 0) {
+        let err = validate_pause_flags(undefined).unwrap_err();
+        assert_eq!(err, GameError::InvalidPauseFlags.into());
+    }
+}
+
+#[test]
+fn refund_paths_never_check_a_pause_bit() {
+    // A leaked backend key is handled by pausing, which must never hold players' or sponsors' funds
+    for refund_path in [
+        "claim_draw_refund",
+        "cancel_game_timeout",
+        "cancel_lobby",
+        "close_lobby",
+        "cancel_tournament",
+        "close_tournament",
+        "settle_bonus",
+        "claim_spectator_payout",
+        "close_spectator_pool",
+    ] {
+        assert!(!handler_source(refund_path).contains("is_paused"), "{refund_path} can be paused");
+    }
+
+    // Inflows and payouts are the ones that can be stopped
+    for paused_path in ["create_lobby", "join_lobby", "sponsor_lobby", "place_spectator_bet", "claim_prize"] {
+        assert!(handler_source(paused_path).contains("is_paused"), "{paused_path} can't be paused");
+    }
+}
+
+#[test]
+fn lobby_timeouts_must_sit_within_the_configured_bounds() {
+    let config = GlobalConfig { min_timeout_seconds: 60, max_timeout_seconds: 600, ..global_config() };
+
+    config.check_timeouts(60, 600).unwrap();
+    config.check_timeouts(300, 300).unwrap();
+
+    for (join_timeout_seconds, play_timeout_seconds) in [(59, 300), (601, 300), (300, 59), (300, 601), (0, 0)] {
+        let err = config.check_timeouts(join_timeout_seconds, play_timeout_seconds).unwrap_err();
+        assert_eq!(err, GameError::InvalidTimeout.into());
+    }
+}
+
+#[test]
+fn timeout_bounds_change_is_validated_before_it_is_queued() {
+    let bounds = |min_timeout_seconds, max_timeout_seconds| ConfigChange::TimeoutBounds {
+        min_timeout_seconds,
+        max_timeout_seconds,
+    };
+
+    validate_config_change(&bounds(1, 1)).unwrap();
+    validate_config_change(&bounds(60, MAX_TIMEOUT_LIMIT_SECONDS)).unwrap();
+
+    // Zero or negative minimum, inverted range, or a maximum past the hard limit
+    for change in [bounds(0, 600), bounds(-60, 600), bounds(600, 60), bounds(60, MAX_TIMEOUT_LIMIT_SECONDS + 1)] {
+        let err = validate_config_change(&change).unwrap_err();
+        assert_eq!(err, GameError::InvalidTimeoutBounds.into());
+    }
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{lobby, player};
+use snake_game::{GameError, Lobby, LobbyStatus, MAX_BONUS_CONTRIBUTIONS};
+
+fn waiting_lobby() -> Lobby {
+    Lobby { status: LobbyStatus::Waiting, game_started_at: None, round_started_at: None, ..lobby(vec![player(0)]) }
+}
+
+#[test]
+fn creator_can_cancel_an_unjoined_lobby() {
+    waiting_lobby().check_creator_cancel().unwrap();
+}
+
+#[test]
+fn cancel_lobby_rejects_a_lobby_someone_joined() {
+    let mut lobby = waiting_lobby();
+    lobby.players.push(player(0));
+
+    let err = lobby.check_creator_cancel().unwrap_err();
+    assert_eq!(err, GameError::PlayersAlreadyJoined.into());
+}
+
+#[test]
+fn cancel_lobby_rejects_a_lobby_with_an_open_sponsorship() {
+    // Closing the lobby would strand the sponsor's contribution, which settle_bonus needs the lobby for
+    let lobby = Lobby { bonus_amount: 50_000_000, open_bonus_contributions: 1, ..waiting_lobby() };
+
+    let err = lobby.check_creator_cancel().unwrap_err();
+    assert_eq!(err, GameError::BonusNotSettled.into());
+}
+
+#[test]
+fn cancel_lobby_rejects_a_started_lobby() {
+    // The creator started it early, so the game is already being played
+    let lobby = lobby(vec![player(0)]);
+
+    let err = lobby.check_creator_cancel().unwrap_err();
+    assert_eq!(err, GameError::LobbyNotAvailable.into());
+}
+
+#[test]
+fn sponsorships_stop_at_the_cap_with_a_dedicated_error() {
+    let mut lobby = waiting_lobby();
+    for _ in 0..MAX_BONUS_CONTRIBUTIONS {
+        lobby.add_bonus(10_000_000).unwrap();
+    }
+    assert_eq!(lobby.open_bonus_contributions, MAX_BONUS_CONTRIBUTIONS);
+    assert_eq!(lobby.bonus_amount, 10_000_000 * MAX_BONUS_CONTRIBUTIONS as u64);
+
+    let err = lobby.add_bonus(10_000_000).unwrap_err();
+    assert_eq!(err, GameError::TooManySponsors.into());
+    assert_eq!(lobby.open_bonus_contributions, MAX_BONUS_CONTRIBUTIONS);
+}
@@ -0,0 +1,197 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::deserialize;
+use anchor_lang::Discriminator;
+use snake_game::{
+    read_v1_account, split_commission, upgrade_v1_account, ContractState, ContractStateV1, GameError, Lobby,
+    LobbyPlayer, LobbyStatus, LobbyV1, BPS_DENOMINATOR, CONTRACT_STATE_VERSION, GAME_TIMEOUT_SECONDS,
+    LOBBY_VERSION,
+};
+
+// Builds raw account data the way a v1 program would have left it on-chain
+fn v1_account_data<T: AnchorSerialize>(discriminator: &[u8], account: &T, space: usize) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+    account.serialize(&mut data).unwrap();
+    assert!(data.len() <= space);
+    data.resize(space, 0);
+    data
+}
+
+// Runs v1 account data through the same upgrade the migrate_* instructions use
+fn migrate<V1: AnchorDeserialize, T: AccountSerialize + AccountDeserialize + From<V1>>(
+    data: &[u8],
+    discriminator: &[u8],
+    v1_space: usize,
+    new_space: usize,
+) -> T {
+    let mut input = common::runtime_input(&[(snake_game::ID, data)]);
+    let (_, accounts, _) = unsafe { deserialize(input.as_mut_ptr() as *mut u8) };
+    let account = &accounts[0];
+
+    upgrade_v1_account::<V1, T>(account, discriminator, v1_space, new_space).unwrap();
+    assert_eq!(account.data_len(), new_space);
+
+    // A second migration of the same account must be rejected
+    let err = read_v1_account::<V1>(account, discriminator, v1_space).err().unwrap();
+    assert_eq!(err, GameError::AccountAlreadyMigrated.into());
+
+    let data = account.try_borrow_data().unwrap();
+    T::try_deserialize(&mut &data[..]).unwrap()
+}
+
+fn migrate_lobby(v1: &LobbyV1) -> Lobby {
+    let data = v1_account_data(Lobby::DISCRIMINATOR, v1, LobbyV1::SPACE);
+    migrate::<LobbyV1, Lobby>(&data, Lobby::DISCRIMINATOR, LobbyV1::SPACE, 8 + Lobby::INIT_SPACE)
+}
+
+fn in_progress_lobby_v1(referrer: Option<Pubkey>) -> LobbyV1 {
+    LobbyV1 {
+        id: "lobby_v1-123".to_string(),
+        creator: Pubkey::new_unique(),
+        opponent: Some(Pubkey::new_unique()),
+        bet_amount: 100_000_000,
+        status: LobbyStatus::InProgress,
+        winner: None,
+        referrer,
+        creator_claimed_draw: None,
+        opponent_claimed_draw: None,
+        commission_taken_draw: false,
+        created_at: 1_700_000_000,
+        game_started_at: Some(1_700_000_100),
+        completed_at: None,
+    }
+}
+
+#[test]
+fn v1_lobby_space_matches_original_layout() {
+    let lobby = LobbyV1 {
+        id: "x".repeat(64),
+        referrer: Some(Pubkey::new_unique()),
+        winner: Some(Pubkey::new_unique()),
+        creator_claimed_draw: Some(true),
+        opponent_claimed_draw: Some(true),
+        completed_at: Some(1_700_000_200),
+        ..in_progress_lobby_v1(None)
+    };
+    let mut data = Lobby::DISCRIMINATOR.to_vec();
+    lobby.serialize(&mut data).unwrap();
+
+    assert_eq!(data.len(), LobbyV1::SPACE);
+}
+
+#[test]
+fn non_v1_sized_account_is_rejected() {
+    let v1 = in_progress_lobby_v1(None);
+    let mut data = v1_account_data(Lobby::DISCRIMINATOR, &v1, LobbyV1::SPACE);
+    data.push(0);
+    let mut input = common::runtime_input(&[(snake_game::ID, &data)]);
+    let (_, accounts, _) = unsafe { deserialize(input.as_mut_ptr() as *mut u8) };
+
+    let err = read_v1_account::<LobbyV1>(&accounts[0], Lobby::DISCRIMINATOR, LobbyV1::SPACE).err().unwrap();
+    assert_eq!(err, GameError::AccountAlreadyMigrated.into());
+
+    let err = read_v1_account::<LobbyV1>(&accounts[0], ContractState::DISCRIMINATOR, LobbyV1::SPACE).err().unwrap();
+    assert_eq!(err, GameError::InvalidAccountVersion.into());
+}
+
+#[test]
+fn v1_in_progress_lobby_settles_at_legacy_terms_after_migration() {
+    let referrer = Pubkey::new_unique();
+    let v1 = in_progress_lobby_v1(Some(referrer));
+    let lobby = migrate_lobby(&v1);
+
+    assert_eq!(lobby.version, LOBBY_VERSION);
+    assert_eq!(lobby.id, v1.id);
+    assert_eq!(lobby.creator, v1.creator);
+    assert_eq!(lobby.bet_amount, v1.bet_amount);
+    assert_eq!(lobby.mint, None);
+    // Both v1 sides become players with their full stake escrowed
+    assert_eq!(lobby.max_players, 2);
+    assert_eq!(
+        lobby.players,
+        vec![
+            LobbyPlayer { player: v1.creator, team: 0, wins: 0, escrowed: v1.bet_amount, claimed: false },
+            LobbyPlayer { player: v1.opponent.unwrap(), team: 0, wins: 0, escrowed: v1.bet_amount, claimed: false },
+        ]
+    );
+    assert!(lobby.status == LobbyStatus::InProgress);
+    assert_eq!(lobby.winner, None);
+    assert_eq!(lobby.forfeited_by, None);
+    assert_eq!(lobby.referrer, Some(referrer));
+    assert_eq!(lobby.game_started_at, v1.game_started_at);
+
+    // A v1 vault holds both stakes above its rent reserve. claim_prize settles it at the snapshotted terms,
+    // which must reproduce the old hardcoded 5% commission split 50-50 with the referrer.
+    let vault_stake_balance = v1.bet_amount * 2;
+    let (total_commission, prizes) = lobby.settlement(vault_stake_balance, &[BPS_DENOMINATOR]).unwrap();
+    assert_eq!(total_commission, vault_stake_balance * 5 / 100);
+    assert_eq!(prizes, vec![vault_stake_balance - vault_stake_balance * 5 / 100]);
+    let (our_commission, referrer_commission) =
+        split_commission(total_commission, lobby.referrer_share_bps, lobby.referrer.is_some()).unwrap();
+    assert_eq!(referrer_commission, total_commission / 2);
+    assert_eq!(our_commission + referrer_commission, total_commission);
+    
+    // Both v1 stakes count as escrowed, so a vault short of either one can't be settled
+    let err = lobby.settlement(vault_stake_balance - 1, &[BPS_DENOMINATOR]).err().unwrap();
+    assert_eq!(err, GameError::InsufficientVaultBalance.into());
+
+    // v1 was always winner takes all
+    assert!(lobby.payout_bps.is_empty());
+    assert!(!lobby.team_mode);
+    assert_eq!(lobby.invite, None);
+    assert_eq!(lobby.series_length, 1);
+    assert_eq!(lobby.bonus_amount, 0);
+    assert_eq!(lobby.round_started_at, v1.game_started_at);
+
+    // Timeout cancels keep the old 60 minute windows
+    assert_eq!(lobby.join_timeout_seconds, GAME_TIMEOUT_SECONDS);
+    assert_eq!(lobby.play_timeout_seconds, GAME_TIMEOUT_SECONDS);
+}
+
+#[test]
+fn v1_draw_lobby_keeps_claim_progress_after_migration() {
+    let v1 = LobbyV1 {
+        status: LobbyStatus::Draw,
+        creator_claimed_draw: Some(true),
+        commission_taken_draw: true,
+        completed_at: Some(1_700_000_200),
+        ..in_progress_lobby_v1(None)
+    };
+    let lobby = migrate_lobby(&v1);
+
+    assert!(lobby.status == LobbyStatus::Draw);
+    assert!(lobby.players[0].claimed);
+    assert!(!lobby.players[1].claimed);
+    assert!(lobby.commission_taken_draw);
+    assert_eq!(lobby.completed_at, v1.completed_at);
+
+    // v1 already paid the commission and the creator's refund out of the vault, leaving exactly what the
+    // opponent is owed: their stake minus half of the legacy 5% commission
+    let v1_commission = v1.bet_amount * 2 * 5 / 100;
+    let vault_stake_balance = v1.bet_amount * 2 - v1_commission - (v1.bet_amount - v1_commission / 2);
+    let (payable_commission, refund_amount) = lobby.draw_refund(1).unwrap();
+    assert_eq!(payable_commission, v1_commission);
+    assert_eq!(refund_amount, vault_stake_balance);
+    // Matches what v1 refunded the creator, so both sides bore the same half of the commission
+    assert_eq!(lobby.draw_refund(0).unwrap().1, v1.bet_amount - v1_commission / 2);
+}
+
+#[test]
+fn v1_contract_state_keeps_accumulated_commission_after_migration() {
+    let v1 = ContractStateV1 { accumulated_commission: 42_000_000 };
+    let data = v1_account_data(ContractState::DISCRIMINATOR, &v1, ContractStateV1::SPACE);
+    assert_eq!(data.len(), ContractStateV1::SPACE);
+
+    let contract_state = migrate::<ContractStateV1, ContractState>(
+        &data,
+        ContractState::DISCRIMINATOR,
+        ContractStateV1::SPACE,
+        8 + ContractState::INIT_SPACE,
+    );
+
+    assert_eq!(contract_state.version, CONTRACT_STATE_VERSION);
+    assert_eq!(contract_state.accumulated_commission, 42_000_000);
+    // v1 only ever escrowed SOL, so there are no token ledgers to carry over
+    assert!(contract_state.token_commissions.is_empty());
+}
@@ -0,0 +1,345 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::{lobby, player, won_by};
+use snake_game::{
+    pool_payouts, refund_commission, split_prize, GameError, Lobby, LobbyStatus, SpectatorBet,
+    SpectatorPool, SpectatorPoolStatus, Tournament, TournamentEntrant, TournamentStatus, BPS_DENOMINATOR,
+    DEFAULT_COMMISSION_BPS, DEFAULT_REFERRER_SHARE_BPS, GAME_TIMEOUT_SECONDS,
+    SPECTATOR_CLAIM_WINDOW_SECONDS,
+};
+
+fn spectator_pool(lobby: &Lobby, backed_totals: Vec<u64>) -> SpectatorPool {
+    SpectatorPool {
+        lobby: Pubkey::new_unique(),
+        lobby_id: lobby.id.clone(),
+        backed_totals,
+        commission_bps: DEFAULT_COMMISSION_BPS,
+        cutoff_at: 1_700_000_100,
+        status: SpectatorPoolStatus::Open,
+        winner_index: None,
+        payout_pool: 0,
+        opener: Pubkey::new_unique(),
+        open_bets: 0,
+        settled_at: None,
+        bump: 255,
+    }
+}
+
+fn bet(player_index: u8, amount: u64) -> SpectatorBet {
+    SpectatorBet { pool: Pubkey::new_unique(), spectator: Pubkey::new_unique(), player_index, amount, bump: 255 }
+}
+
+// Finished 8 player bracket: entrant 5 won the final against entrant 2, entrants 0 and 7 lost the semifinals
+fn finished_tournament(payout_bps: Vec<u16>) -> Tournament {
+    let eliminated_in = [Some(2), Some(1), Some(3), Some(1), Some(1), None, Some(1), Some(2)];
+    Tournament {
+        id: "cup-1".to_string(),
+        organizer: Pubkey::new_unique(),
+        entry_fee: 100_000_000,
+        mint: None,
+        max_entrants: 8,
+        entrants: eliminated_in
+            .iter()
+            .map(|eliminated_in| TournamentEntrant {
+                player: Pubkey::new_unique(),
+                escrowed: 100_000_000,
+                eliminated_in: *eliminated_in,
+            })
+            .collect(),
+        payout_bps,
+        match_winners: Vec::new(),
+        round: 3,
+        status: TournamentStatus::Completed,
+        referrer: None,
+        commission_bps: DEFAULT_COMMISSION_BPS,
+        referrer_share_bps: DEFAULT_REFERRER_SHARE_BPS,
+        registration_timeout_seconds: GAME_TIMEOUT_SECONDS,
+        match_timeout_seconds: GAME_TIMEOUT_SECONDS,
+        created_at: 1_700_000_000,
+        started_at: Some(1_700_000_100),
+        round_started_at: Some(1_700_000_300),
+        completed_at: Some(1_700_000_400),
+        bump: 255,
+    }
+}
+
+#[test]
+fn split_prize_gives_rounding_dust_to_first_place() {
+    let prizes = split_prize(1_000_003, &[5_000, 3_000, 2_000]).unwrap();
+
+    assert_eq!(prizes, vec![500_003, 300_000, 200_000]);
+    assert_eq!(prizes.iter().sum::<u64>(), 1_000_003);
+}
+
+#[test]
+fn split_prize_rejects_an_empty_payout_table() {
+    let err = split_prize(1_000_000, &[]).err().unwrap();
+    assert_eq!(err, GameError::InvalidPayoutTable.into());
+}
+
+#[test]
+fn pool_payouts_always_add_up_to_the_pot() {
+    let tables: [&[u16]; 4] = [&[BPS_DENOMINATOR], &[6_000, 4_000], &[5_000, 3_000, 2_000], &[3_334, 3_333, 3_333]];
+    for total_pool in [0, 1, 7, 99, 10_001, 123_456_789, u64::MAX / BPS_DENOMINATOR as u64] {
+        for shares_bps in tables {
+            for commission_bps in [0, 250, 500, 1_000] {
+                let (total_commission, prizes) = pool_payouts(total_pool, commission_bps, shares_bps).unwrap();
+
+                assert_eq!(prizes.len(), shares_bps.len());
+                assert_eq!(total_commission + prizes.iter().sum::<u64>(), total_pool);
+                // Commission is rounded down, so it never exceeds the configured rate
+                assert!(total_commission as u128 * BPS_DENOMINATOR as u128 <= total_pool as u128 * commission_bps as u128);
+            }
+        }
+    }
+}
+
+#[test]
+fn pool_payouts_take_commission_once_from_the_whole_pool() {
+    let (total_commission, prizes) = pool_payouts(300_000_000, 500, &[5_000, 3_000, 2_000]).unwrap();
+
+    assert_eq!(total_commission, 15_000_000);
+    assert_eq!(prizes, vec![142_500_000, 85_500_000, 57_000_000]);
+}
+
+#[test]
+fn refund_commission_splits_evenly_when_it_divides() {
+    let (payable_commission, commission_per_player) = refund_commission(10_000_000, 2).unwrap();
+
+    assert_eq!(payable_commission, 10_000_000);
+    assert_eq!(commission_per_player, 5_000_000);
+}
+
+#[test]
+fn refund_commission_never_leaves_the_vault_short() {
+    for total_commission in [0, 1, 2, 10, 1_001, 15_000_001] {
+        for player_count in 1..=8usize {
+            let (payable_commission, commission_per_player) = refund_commission(total_commission, player_count).unwrap();
+            let charged = commission_per_player * player_count as u64;
+
+            // Only the evenly divisible part is paid out, and every player is charged at least their share of it
+            assert_eq!(payable_commission % player_count as u64, 0);
+            assert!(total_commission - payable_commission < player_count as u64);
+            assert!(charged >= payable_commission);
+            assert!(charged - total_commission < player_count as u64);
+        }
+    }
+}
+
+#[test]
+fn refund_commission_rejects_a_lobby_without_players() {
+    let err = refund_commission(10_000_000, 0).err().unwrap();
+    assert_eq!(err, GameError::ArithmeticOverflow.into());
+}
+
+#[test]
+fn team_payouts_split_equally_between_winning_members_in_join_order() {
+    let lobby = Lobby { team_mode: true, ..lobby(vec![player(0), player(1), player(1), player(0)]) };
+    let (winners, shares_bps) = lobby.team_payouts(1);
+
+    assert_eq!(winners, vec![lobby.players[1].player, lobby.players[2].player]);
+    assert_eq!(shares_bps, vec![BPS_DENOMINATOR / 2; 2]);
+    assert_eq!(shares_bps.iter().sum::<u16>(), BPS_DENOMINATOR);
+}
+
+#[test]
+fn team_prize_dust_goes_to_the_first_member() {
+    let lobby = Lobby { team_mode: true, ..lobby(vec![player(0), player(1), player(1), player(0)]) };
+    let (_, shares_bps) = lobby.team_payouts(0);
+    let total_pool = lobby.total_escrowed().unwrap() + 3;
+
+    let (total_commission, prizes) = pool_payouts(total_pool, lobby.commission_bps, &shares_bps).unwrap();
+
+    assert_eq!(prizes[0] - prizes[1], 1);
+    assert_eq!(total_commission + prizes.iter().sum::<u64>(), total_pool);
+}
+
+#[test]
+fn team_without_members_cannot_be_paid() {
+    let lobby = Lobby { team_mode: true, ..lobby(vec![player(0), player(1), player(1), player(0)]) };
+    let (winners, shares_bps) = lobby.team_payouts(2);
+
+    assert!(winners.is_empty());
+    let err = pool_payouts(lobby.total_escrowed().unwrap(), lobby.commission_bps, &shares_bps).err().unwrap();
+    assert_eq!(err, GameError::InvalidPayoutTable.into());
+}
+
+#[test]
+fn drawn_series_rounds_are_replayed_without_awarding_wins() {
+    let mut lobby = Lobby { series_length: 3, ..lobby(vec![player(0), player(0)]) };
+    let (first, second) = (lobby.players[0].player, lobby.players[1].player);
+    assert_eq!(lobby.wins_required(), 2);
+
+    assert_eq!(lobby.apply_round(Some(first), 1_700_000_200).unwrap(), None);
+    assert_eq!(lobby.apply_round(None, 1_700_000_300).unwrap(), None);
+    assert_eq!(lobby.apply_round(Some(second), 1_700_000_400).unwrap(), None);
+    assert_eq!(lobby.apply_round(None, 1_700_000_500).unwrap(), None);
+
+    // Draws count as played and restart the play timeout, but only wins decide the series
+    assert_eq!(lobby.rounds_played, 4);
+    assert_eq!(lobby.round_started_at, Some(1_700_000_500));
+    assert_eq!(lobby.players.iter().map(|entry| entry.wins).collect::<Vec<_>>(), vec![1, 1]);
+
+    assert_eq!(lobby.apply_round(Some(second), 1_700_000_600).unwrap(), Some(second));
+    assert_eq!(lobby.rounds_played, 5);
+}
+
+#[test]
+fn series_round_winner_must_be_a_player() {
+    let mut lobby = Lobby { series_length: 3, ..lobby(vec![player(0), player(0)]) };
+
+    let err = lobby.apply_round(Some(Pubkey::new_unique()), 1_700_000_200).err().unwrap();
+    assert_eq!(err, GameError::InvalidWinner.into());
+    assert_eq!(lobby.rounds_played, 0);
+}
+
+#[test]
+fn placement_shares_rank_by_elimination_round_and_split_tied_places() {
+    let tournament = finished_tournament(vec![5_000, 3_000, 1_500, 500]);
+    let players = tournament.entrants.iter().map(|entrant| entrant.player).collect::<Vec<_>>();
+
+    // Both semifinal losers share third and fourth place equally, in join order
+    assert_eq!(
+        tournament.placement_shares(),
+        vec![(players[5], 5_000), (players[2], 3_000), (players[0], 1_000), (players[7], 1_000)]
+    );
+}
+
+#[test]
+fn placement_shares_give_tied_rounding_dust_to_the_first_entrant() {
+    let tournament = finished_tournament(vec![6_000, 2_499, 1_001, 500]);
+    let shares = tournament.placement_shares();
+
+    assert_eq!(shares[2].1, 751);
+    assert_eq!(shares[3].1, 750);
+    assert_eq!(shares.iter().map(|(_, share_bps)| *share_bps as u32).sum::<u32>(), BPS_DENOMINATOR as u32);
+}
+
+#[test]
+fn placement_shares_split_a_partly_paid_tier_and_skip_empty_shares() {
+    // Only third place is paid, so the two semifinal losers split it
+    let tournament = finished_tournament(vec![6_000, 3_000, 1_000]);
+    let shares = tournament.placement_shares();
+    assert_eq!(shares.iter().map(|(_, share_bps)| *share_bps).collect::<Vec<_>>(), vec![6_000, 3_000, 500, 500]);
+
+    // A single basis point can't be split, so the second semifinal loser gets no payout at all
+    let tournament = finished_tournament(vec![5_000, 4_999, 1]);
+    let shares = tournament.placement_shares();
+    assert_eq!(shares.iter().map(|(_, share_bps)| *share_bps).collect::<Vec<_>>(), vec![5_000, 4_999, 1]);
+    assert_eq!(shares[2].0, tournament.entrants[0].player);
+}
+
+#[test]
+fn tournament_prizes_add_up_to_the_pot() {
+    let tournament = finished_tournament(vec![5_000, 3_000, 1_500, 500]);
+    let shares_bps = tournament.placement_shares().iter().map(|(_, share_bps)| *share_bps).collect::<Vec<_>>();
+    let total_pool = tournament.total_escrowed().unwrap() + 7;
+
+    let (total_commission, prizes) = pool_payouts(total_pool, tournament.commission_bps, &shares_bps).unwrap();
+
+    assert_eq!(total_commission, total_pool * DEFAULT_COMMISSION_BPS as u64 / BPS_DENOMINATOR as u64);
+    assert_eq!(total_commission + prizes.iter().sum::<u64>(), total_pool);
+    assert_eq!(prizes[2], prizes[3]);
+}
+
+#[test]
+fn forfeited_game_voids_the_spectator_pool() {
+    // The forfeiting player backed their opponent from another wallet before conceding
+    let lobby = won_by(lobby(vec![player(0), player(0)]), 1, Some(0));
+    let mut pool = spectator_pool(&lobby, vec![100_000_000, 400_000_000]);
+
+    assert_eq!(pool.outcome(&lobby).unwrap(), None);
+
+    pool.status = SpectatorPoolStatus::Void;
+    let colluding_bet = bet(1, 400_000_000);
+    assert_eq!(pool.payout(&colluding_bet).unwrap(), colluding_bet.amount);
+    assert_eq!(pool.payout(&bet(0, 100_000_000)).unwrap(), 100_000_000);
+}
+
+#[test]
+fn forfeited_game_returns_sponsor_bonuses() {
+    // Two colluding players can't capture a third party's bonus by having one concede
+    let forfeited = won_by(lobby(vec![player(0), player(0)]), 1, Some(0));
+    assert_eq!(forfeited.bonus_winner().unwrap(), None);
+
+    let played = won_by(lobby(vec![player(0), player(0)]), 1, None);
+    assert_eq!(played.bonus_winner().unwrap(), played.winner);
+
+    let drawn = Lobby { status: LobbyStatus::Draw, winner: None, ..lobby(vec![player(0), player(0)]) };
+    assert_eq!(drawn.bonus_winner().unwrap(), None);
+}
+
+#[test]
+fn played_game_pays_the_winners_backers_pro_rata() {
+    let lobby = won_by(lobby(vec![player(0), player(0)]), 1, None);
+    let mut pool = spectator_pool(&lobby, vec![100_000_000, 300_000_000]);
+
+    assert_eq!(pool.outcome(&lobby).unwrap(), Some(1));
+
+    let (_, prizes) = pool_payouts(400_000_000, pool.commission_bps, &[BPS_DENOMINATOR]).unwrap();
+    pool.status = SpectatorPoolStatus::Settled;
+    pool.winner_index = Some(1);
+    pool.payout_pool = prizes[0];
+
+    assert_eq!(pool.payout(&bet(1, 100_000_000)).unwrap(), 126_666_666);
+    assert_eq!(pool.payout(&bet(1, 200_000_000)).unwrap(), 253_333_333);
+    assert_eq!(pool.payout(&bet(0, 100_000_000)).unwrap(), 0);
+}
+
+#[test]
+fn unbacked_winner_or_unfinished_game_does_not_settle_the_pool() {
+    let mut lobby = won_by(lobby(vec![player(0), player(0)]), 1, None);
+    let pool = spectator_pool(&lobby, vec![100_000_000, 0]);
+    assert_eq!(pool.outcome(&lobby).unwrap(), None);
+    let err = pool.payout(&bet(0, 100_000_000)).err().unwrap();
+    assert_eq!(err, GameError::SpectatorPoolNotSettled.into());
+
+    lobby.status = LobbyStatus::InProgress;
+    let err = pool.outcome(&lobby).err().unwrap();
+    assert_eq!(err, GameError::GameNotFinished.into());
+}
+
+#[test]
+fn settled_pool_can_be_swept_once_the_claim_window_ends() {
+    let lobby = won_by(lobby(vec![player(0), player(0)]), 1, None);
+    let settled_at = 1_700_000_300;
+    let pool = SpectatorPool {
+        status: SpectatorPoolStatus::Settled,
+        winner_index: Some(1),
+        open_bets: 2,
+        settled_at: Some(settled_at),
+        ..spectator_pool(&lobby, vec![100_000_000, 300_000_000])
+    };
+
+    let err = pool.check_closable(settled_at + SPECTATOR_CLAIM_WINDOW_SECONDS - 1).err().unwrap();
+    assert_eq!(err, GameError::SpectatorClaimsOutstanding.into());
+    pool.check_closable(settled_at + SPECTATOR_CLAIM_WINDOW_SECONDS).unwrap();
+}
+
+#[test]
+fn void_pool_refunds_are_never_swept() {
+    let lobby = won_by(lobby(vec![player(0), player(0)]), 1, Some(0));
+    let mut pool = SpectatorPool {
+        status: SpectatorPoolStatus::Void,
+        open_bets: 1,
+        settled_at: Some(1_700_000_300),
+        ..spectator_pool(&lobby, vec![100_000_000, 400_000_000])
+    };
+
+    // However long the refund sits unclaimed, the pool stays open until its backer collects it
+    let err = pool.check_closable(i64::MAX).err().unwrap();
+    assert_eq!(err, GameError::SpectatorClaimsOutstanding.into());
+
+    pool.open_bets = 0;
+    pool.check_closable(1_700_000_300).unwrap();
+}
+
+#[test]
+fn open_pool_cannot_be_closed() {
+    let lobby = won_by(lobby(vec![player(0), player(0)]), 1, None);
+    let pool = spectator_pool(&lobby, vec![0, 0]);
+
+    let err = pool.check_closable(i64::MAX).err().unwrap();
+    assert_eq!(err, GameError::SpectatorPoolNotSettled.into());
+}
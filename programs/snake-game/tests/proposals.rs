@@ -0,0 +1,111 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::deserialize;
+use anchor_lang::AccountsClose;
+use snake_game::{start_proposal_timelock, GameError, Multisig, Proposal, ProposalAction};
+
+const NOW: i64 = 1_700_000_000;
+const TIMELOCK_DELAY: i64 = 86_400;
+
+fn multisig(signers: &[Pubkey], threshold: u8) -> Multisig {
+    Multisig { signers: signers.to_vec(), threshold, proposal_count: 1, bump: 255 }
+}
+
+fn proposal(id: u64, action: ProposalAction, approvals: &[Pubkey], eta: Option<i64>) -> Proposal {
+    Proposal {
+        id,
+        proposer: approvals[0],
+        action,
+        approvals: approvals.to_vec(),
+        executed: false,
+        created_at: NOW,
+        eta,
+        bump: 255,
+    }
+}
+
+#[test]
+fn cancelled_proposal_cannot_execute() {
+    let signers = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let target = proposal(
+        0,
+        ProposalAction::TransferAdmin { new_admin: Pubkey::new_unique() },
+        &signers[..2],
+        Some(NOW + TIMELOCK_DELAY),
+    );
+
+    // Still inside its notice period, so cancel_proposal accepts it
+    target.check_cancellable(NOW).unwrap();
+
+    let mut data = Vec::new();
+    target.try_serialize(&mut data).unwrap();
+    data.resize(8 + Proposal::INIT_SPACE, 0);
+    let mut input = common::runtime_input(&[(snake_game::ID, &data), (System::id(), &[])]);
+    let (_, accounts, _) = unsafe { deserialize(input.as_mut_ptr() as *mut u8) };
+
+    // Same close the cancel_proposal accounts run on the target
+    let account = Account::<Proposal>::try_from(&accounts[0]).unwrap();
+    account.close(accounts[1].clone()).unwrap();
+
+    // execute_proposal can no longer load the proposal, so the queued action never applies
+    assert_eq!(accounts[0].lamports(), 0);
+    assert!(Account::<Proposal>::try_from(&accounts[0]).is_err());
+}
+
+#[test]
+fn proposal_cannot_be_cancelled_once_its_notice_period_ends() {
+    let signers = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut queued = proposal(
+        0,
+        ProposalAction::SetCommissionClaimer { new_commission_claimer: Pubkey::new_unique() },
+        &signers,
+        Some(NOW + TIMELOCK_DELAY),
+    );
+
+    queued.check_cancellable(NOW + TIMELOCK_DELAY - 1).unwrap();
+    let err = queued.check_cancellable(NOW + TIMELOCK_DELAY).unwrap_err();
+    assert_eq!(err, GameError::CancelWindowClosed.into());
+
+    queued.executed = true;
+    let err = queued.check_cancellable(NOW).unwrap_err();
+    assert_eq!(err, GameError::ProposalAlreadyExecuted.into());
+}
+
+#[test]
+fn unqueued_proposal_can_be_cancelled() {
+    let signers = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let pending = proposal(
+        0,
+        ProposalAction::UpdateMultisig { signers: signers.to_vec(), threshold: 3 },
+        &signers[..1],
+        None,
+    );
+
+    // Not yet approved to the threshold, so there is no notice period to wait out
+    pending.check_cancellable(NOW + 10 * TIMELOCK_DELAY).unwrap();
+}
+
+#[test]
+fn lowered_threshold_starts_the_timelock_for_already_approved_proposals() {
+    let signers = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut pending = proposal(
+        0,
+        ProposalAction::SetOracles { oracles: vec![Pubkey::new_unique()], threshold: 1 },
+        &signers[..2],
+        None,
+    );
+
+    // Two of three approvals don't queue it while the threshold is three
+    start_proposal_timelock(&mut pending, &multisig(&signers, 3), TIMELOCK_DELAY, NOW).unwrap();
+    assert_eq!(pending.eta, None);
+
+    // After UpdateMultisig lowers the threshold, execute_proposal starts the notice period instead of executing
+    let lowered = multisig(&signers, 2);
+    start_proposal_timelock(&mut pending, &lowered, TIMELOCK_DELAY, NOW + 100).unwrap();
+    assert_eq!(pending.eta, Some(NOW + 100 + TIMELOCK_DELAY));
+
+    // Once started the eta is fixed
+    start_proposal_timelock(&mut pending, &lowered, TIMELOCK_DELAY, NOW + 200).unwrap();
+    assert_eq!(pending.eta, Some(NOW + 100 + TIMELOCK_DELAY));
+}
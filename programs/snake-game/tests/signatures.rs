@@ -0,0 +1,152 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::{global_config, lobby, player};
+use snake_game::{check_invite_signature, check_oracle_signatures, GameError, GlobalConfig};
+
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+const MESSAGE: &[u8] = b"lobby_1:winner";
+
+// Ed25519 precompile data with every key, signature and message stored inline after the offsets
+fn ed25519_data(entries: &[(Pubkey, [u8; 64], &[u8])], instruction_index: u16) -> Vec<u8> {
+    let mut data = vec![entries.len() as u8, 0];
+    let mut payload = Vec::new();
+    let payload_start = 2 + entries.len() * 14;
+    for (signer, signature, message) in entries {
+        let public_key_offset = payload_start + payload.len();
+        payload.extend_from_slice(signer.as_ref());
+        let signature_offset = payload_start + payload.len();
+        payload.extend_from_slice(signature);
+        let message_offset = payload_start + payload.len();
+        payload.extend_from_slice(message);
+
+        for field in [
+            signature_offset as u16,
+            instruction_index,
+            public_key_offset as u16,
+            instruction_index,
+            message_offset as u16,
+            message.len() as u16,
+            instruction_index,
+        ] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+    }
+    data.extend_from_slice(&payload);
+    data
+}
+
+fn oracles(count: usize, threshold: u8) -> GlobalConfig {
+    GlobalConfig {
+        oracles: (0..count).map(|_| Pubkey::new_unique()).collect(),
+        oracle_threshold: threshold,
+        ..global_config()
+    }
+}
+
+#[test]
+fn threshold_of_distinct_oracles_accepts_the_result() {
+    let config = oracles(3, 2);
+    let signatures = [[1u8; 64], [2u8; 64]];
+    let data = ed25519_data(
+        &[(config.oracles[0], signatures[0], MESSAGE), (config.oracles[2], signatures[1], MESSAGE)],
+        CURRENT_INSTRUCTION,
+    );
+
+    check_oracle_signatures(&data, &config, MESSAGE, &signatures).unwrap();
+}
+
+#[test]
+fn fewer_oracles_than_the_threshold_are_rejected() {
+    let config = oracles(3, 2);
+    let signatures = [[1u8; 64]];
+    let data = ed25519_data(&[(config.oracles[1], signatures[0], MESSAGE)], CURRENT_INSTRUCTION);
+
+    let err = check_oracle_signatures(&data, &config, MESSAGE, &signatures).unwrap_err();
+    assert_eq!(err, GameError::OracleThresholdNotMet.into());
+}
+
+#[test]
+fn one_oracle_signing_twice_counts_once() {
+    let config = oracles(3, 2);
+    let signatures = [[1u8; 64], [2u8; 64]];
+    let data = ed25519_data(
+        &[(config.oracles[0], signatures[0], MESSAGE), (config.oracles[0], signatures[1], MESSAGE)],
+        CURRENT_INSTRUCTION,
+    );
+
+    let err = check_oracle_signatures(&data, &config, MESSAGE, &signatures).unwrap_err();
+    assert_eq!(err, GameError::DuplicateOracleSignature.into());
+}
+
+#[test]
+fn signer_outside_the_oracle_set_is_rejected() {
+    let config = oracles(2, 1);
+    let signatures = [[1u8; 64]];
+    let data = ed25519_data(&[(Pubkey::new_unique(), signatures[0], MESSAGE)], CURRENT_INSTRUCTION);
+
+    let err = check_oracle_signatures(&data, &config, MESSAGE, &signatures).unwrap_err();
+    assert_eq!(err, GameError::UnknownOracle.into());
+}
+
+#[test]
+fn signatures_must_cover_this_result_and_live_in_the_precompile_instruction() {
+    let config = oracles(1, 1);
+    let signatures = [[1u8; 64]];
+    let signed = ed25519_data(&[(config.oracles[0], signatures[0], MESSAGE)], CURRENT_INSTRUCTION);
+
+    // Signed a different result
+    let other = ed25519_data(&[(config.oracles[0], signatures[0], b"lobby_1:draw")], CURRENT_INSTRUCTION);
+    let err = check_oracle_signatures(&other, &config, MESSAGE, &signatures).unwrap_err();
+    assert_eq!(err, GameError::InvalidSignature.into());
+
+    // Signature passed to the program isn't the one the precompile verified
+    let err = check_oracle_signatures(&signed, &config, MESSAGE, &[[9u8; 64]]).unwrap_err();
+    assert_eq!(err, GameError::InvalidSignature.into());
+
+    // Signature count doesn't match
+    let err = check_oracle_signatures(&signed, &config, MESSAGE, &[[1u8; 64], [2u8; 64]]).unwrap_err();
+    assert_eq!(err, GameError::InvalidSignature.into());
+
+    // Offsets pointing into another instruction
+    let elsewhere = ed25519_data(&[(config.oracles[0], signatures[0], MESSAGE)], 0);
+    let err = check_oracle_signatures(&elsewhere, &config, MESSAGE, &signatures).unwrap_err();
+    assert_eq!(err, GameError::InvalidSignature.into());
+
+    // Truncated before the offsets end
+    let err = check_oracle_signatures(&signed[..10], &config, MESSAGE, &signatures).unwrap_err();
+    assert_eq!(err, GameError::InvalidSignature.into());
+}
+
+#[test]
+fn invite_signature_only_admits_the_joiner_it_names() {
+    let invite_key = Pubkey::new_unique();
+    let private = lobby(vec![player(0)]);
+    let joiner = Pubkey::new_unique();
+    let message = private.invite_message(&joiner);
+    let data = ed25519_data(&[(invite_key, [1u8; 64], message.as_bytes())], CURRENT_INSTRUCTION);
+
+    check_invite_signature(&data, &invite_key, message.as_bytes()).unwrap();
+
+    // Lifted from the joiner's pending transaction by someone else
+    let other = private.invite_message(&Pubkey::new_unique());
+    let err = check_invite_signature(&data, &invite_key, other.as_bytes()).unwrap_err();
+    assert_eq!(err, GameError::InvalidInviteSignature.into());
+
+    // Signed by a key other than the lobby's invite key
+    let err = check_invite_signature(&data, &Pubkey::new_unique(), message.as_bytes()).unwrap_err();
+    assert_eq!(err, GameError::InvalidInviteSignature.into());
+}
+
+#[test]
+fn invite_needs_exactly_one_signature() {
+    let invite_key = Pubkey::new_unique();
+    let message = lobby(vec![player(0)]).invite_message(&Pubkey::new_unique());
+    let data = ed25519_data(
+        &[(invite_key, [1u8; 64], message.as_bytes()), (invite_key, [2u8; 64], message.as_bytes())],
+        CURRENT_INSTRUCTION,
+    );
+
+    let err = check_invite_signature(&data, &invite_key, message.as_bytes()).unwrap_err();
+    assert_eq!(err, GameError::InvalidSignature.into());
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::{lobby, player};
+use snake_game::{
+    calculate_commission, CommissionLedger, ContractState, GameError, Lobby, LobbyStatus, BPS_DENOMINATOR,
+    CONTRACT_STATE_VERSION,
+};
+
+const BET_AMOUNT: u64 = 100_000_000;
+
+// Token lobby on a transfer-fee mint, each player's vault deposit arrived short by the fee
+fn fee_mint_lobby(escrowed: [u64; 2]) -> Lobby {
+    let mut players = vec![player(0), player(0)];
+    for (entry, escrowed) in players.iter_mut().zip(escrowed) {
+        entry.escrowed = escrowed;
+    }
+    Lobby { mint: Some(Pubkey::new_unique()), bet_amount: BET_AMOUNT, ..lobby(players) }
+}
+
+#[test]
+fn fee_mint_lobby_settles_what_the_vault_received() {
+    let lobby = fee_mint_lobby([99_000_000, 99_000_000]);
+    let vault_stake_balance = 198_000_000;
+
+    let (total_commission, prizes) = lobby.settlement(vault_stake_balance, &[BPS_DENOMINATOR]).unwrap();
+
+    // Commission and prize come out of the received stakes, not twice the nominal bet
+    assert_eq!(total_commission, calculate_commission(vault_stake_balance, lobby.commission_bps).unwrap());
+    assert_eq!(total_commission + prizes[0], vault_stake_balance);
+}
+
+#[test]
+fn fee_mint_lobby_refuses_to_settle_a_short_vault() {
+    let lobby = fee_mint_lobby([99_000_000, 99_000_000]);
+
+    let err = lobby.settlement(197_999_999, &[BPS_DENOMINATOR]).unwrap_err();
+    assert_eq!(err, GameError::InsufficientVaultBalance.into());
+}
+
+#[test]
+fn fee_mint_draw_refunds_return_each_players_own_escrow() {
+    let lobby = Lobby { status: LobbyStatus::Draw, ..fee_mint_lobby([99_000_000, 99_500_000]) };
+    let total_commission = calculate_commission(lobby.total_escrowed().unwrap(), lobby.commission_bps).unwrap();
+
+    let (payable_commission, first_refund) = lobby.draw_refund(0).unwrap();
+    let (_, second_refund) = lobby.draw_refund(1).unwrap();
+
+    // Both pay the same commission share, so the refunds differ by exactly what their deposits did
+    assert_eq!(second_refund - first_refund, 500_000);
+    assert!(payable_commission <= total_commission);
+    assert!(payable_commission + first_refund + second_refund <= lobby.total_escrowed().unwrap());
+}
+
+#[test]
+fn commission_is_credited_to_its_own_asset_ledger() {
+    let usdc = Pubkey::new_unique();
+    let bonk = Pubkey::new_unique();
+    let mut state = ContractState {
+        version: CONTRACT_STATE_VERSION,
+        accumulated_commission: 0,
+        token_commissions: [usdc, bonk]
+            .map(|mint| CommissionLedger { mint, accumulated_commission: 0 })
+            .to_vec(),
+    };
+
+    state.credit_commission(None, 5_000_000).unwrap();
+    state.credit_commission(Some(usdc), 2_000).unwrap();
+    state.credit_commission(Some(usdc), 500).unwrap();
+
+    assert_eq!(state.accumulated_commission, 5_000_000);
+    assert_eq!(*state.commission_ledger(Some(usdc)).unwrap(), 2_500);
+    assert_eq!(*state.commission_ledger(Some(bonk)).unwrap(), 0);
+
+    // A mint without a ledger was never allowed, so it can't have earned commission
+    let err = state.credit_commission(Some(Pubkey::new_unique()), 1).unwrap_err();
+    assert_eq!(err, GameError::MintNotAllowed.into());
+}
+
+#[test]
+fn commission_is_debited_only_from_its_own_asset_ledger() {
+    let usdc = Pubkey::new_unique();
+    let mut state = ContractState {
+        version: CONTRACT_STATE_VERSION,
+        accumulated_commission: 5_000_000,
+        token_commissions: vec![CommissionLedger { mint: usdc, accumulated_commission: 2_500 }],
+    };
+
+    // Plenty of SOL commission doesn't cover a token withdrawal
+    let err = state.debit_commission(Some(usdc), 2_501).unwrap_err();
+    assert_eq!(err, GameError::InsufficientCommission.into());
+
+    state.debit_commission(Some(usdc), 2_500).unwrap();
+    state.debit_commission(None, 1_000_000).unwrap();
+    assert_eq!(*state.commission_ledger(Some(usdc)).unwrap(), 0);
+    assert_eq!(state.accumulated_commission, 4_000_000);
+}